@@ -0,0 +1,382 @@
+// ability.rs
+//
+// Spells and special abilities, loaded from `data/abilities.txt`. An ability
+// has a cost, a shape that picks out the tiles it hits and a list of effects
+// applied to whatever is on them. `Spellbook` tracks what a caster knows,
+// their mana and their cooldowns.
+use std::collections::HashMap;
+use crate::data::{self, DataError, Field, Section};
+use crate::geometry::{self, Pos};
+use crate::map::MapManager;
+use crate::map::tile::Tile;
+use crate::status::{StatusEffect, StatusKind};
+
+const EMBEDDED: &str = include_str!("../data/abilities.txt");
+/// Half the width of a cone, in degrees.
+const CONE_HALF_ANGLE: f32 = 30.0;
+/// Caster turns it takes to get a point of mana back.
+const MANA_REGEN_TURNS: u32 = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Cost {
+    Mana(i32),
+    /// Turns before it can be used again.
+    Cooldown(u64),
+}
+
+/// Which tiles an ability hits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Shape {
+    /// Just the caster.
+    Caster,
+    Single { range: i32 },
+    Line { range: i32 },
+    Cone { range: i32 },
+    Burst { range: i32, radius: i32 },
+}
+
+impl Shape {
+    /// Whether something cast from `from` can be aimed at `to`. Lines and
+    /// cones only need a direction; the rest need a target in range and in
+    /// sight.
+    pub fn can_target(&self, map: &MapManager, from: Pos, to: Pos) -> bool {
+        match *self {
+            Shape::Caster => true,
+            Shape::Line { .. } | Shape::Cone { .. } => to != from,
+            Shape::Single { range } | Shape::Burst { range, .. } => {
+                from.distance_sq(to) <= range * range && map.has_line_of_sight(from, to)
+            }
+        }
+    }
+
+    /// The tiles hit when cast from `from` at `to`; empty if `to` can't be
+    /// targeted.
+    pub fn area(&self, map: &MapManager, from: Pos, to: Pos) -> Vec<Pos> {
+        if !self.can_target(map, from, to) {
+            return Vec::new();
+        }
+        match *self {
+            Shape::Caster => vec![from],
+            Shape::Single { .. } => vec![to],
+            Shape::Line { range } => map.cast_ray(from, extend(from, to, range)).path,
+            Shape::Cone { range } => {
+                let aim = to - from;
+                let min_cos = CONE_HALF_ANGLE.to_radians().cos();
+                geometry::circle(from, range)
+                    .filter(|p| *p != from)
+                    .filter(|p| {
+                        let d = *p - from;
+                        let dot = (d.x * aim.x + d.y * aim.y) as f32;
+                        dot / ((d.x * d.x + d.y * d.y) as f32 * (aim.x * aim.x + aim.y * aim.y) as f32).sqrt() >= min_cos
+                    })
+                    .filter(|p| map.has_line_of_sight(from, *p))
+                    .collect()
+            }
+            Shape::Burst { radius, .. } => geometry::circle(to, radius)
+                .filter(|p| map.has_line_of_sight(to, *p))
+                .collect(),
+        }
+    }
+}
+
+/// The point `range` tiles from `from` in the direction of `to`.
+fn extend(from: Pos, to: Pos, range: i32) -> Pos {
+    let d = to - from;
+    let scale = range as f32 / ((d.x * d.x + d.y * d.y) as f32).sqrt();
+    from + Pos::new((d.x as f32 * scale).round() as i32, (d.y as f32 * scale).round() as i32)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AbilityEffect {
+    Damage(i32),
+    Heal(i32),
+    Apply(StatusEffect),
+    /// Moves the caster to the target tile.
+    Teleport,
+    /// Turns every tile hit into the standard tile drawn with this character.
+    Terrain(char),
+}
+
+#[derive(Clone, Debug)]
+pub struct Ability {
+    pub id: String,
+    pub name: String,
+    pub cost: Cost,
+    pub shape: Shape,
+    pub effects: Vec<AbilityEffect>,
+    /// Level at which the player learns it, if they ever do.
+    pub learn: Option<i32>,
+}
+
+impl Ability {
+    /// Short summary of the cost, like "3 mana" or "30 turn cooldown".
+    pub fn describe_cost(&self) -> String {
+        match self.cost {
+            Cost::Mana(n) => format!("{} mana", n),
+            Cost::Cooldown(n) => format!("{} turn cooldown", n),
+        }
+    }
+
+    /// Whether it's meant to be aimed at enemies rather than used on
+    /// oneself or the terrain.
+    pub fn is_offensive(&self) -> bool {
+        self.shape != Shape::Caster
+            && self.effects.iter().any(|e| matches!(e, AbilityEffect::Damage(_) | AbilityEffect::Apply(_)))
+    }
+}
+
+pub struct Abilities {
+    /// In file order, which is the order the player learns them in.
+    list: Vec<Ability>,
+}
+
+impl Abilities {
+    /// Loads `data/abilities.txt`, falling back to the built-in copy. Also
+    /// returns a warning if the file on disk had to be ignored.
+    pub fn load() -> (Self, Option<String>) {
+        data::load("abilities.txt", EMBEDDED, Self::from_sections)
+    }
+
+    pub fn from_sections(sections: &[Section]) -> Result<Self, DataError> {
+        let mut list: Vec<Ability> = Vec::new();
+        for section in sections {
+            if section.kind != "ability" {
+                return Err(section.error(format!("unknown section kind '{}'", section.kind)));
+            }
+            if list.iter().any(|a| a.id == section.name) {
+                return Err(section.error(format!("ability '{}' is defined twice", section.name)));
+            }
+            list.push(parse_ability(section)?);
+        }
+        Ok(Self { list })
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Ability> {
+        self.list.iter().find(|a| a.id == id)
+    }
+
+    /// Abilities the player knows by `level`.
+    pub fn learned_by(&self, level: i32) -> impl Iterator<Item = &Ability> {
+        self.list.iter().filter(move |a| a.learn.is_some_and(|l| l <= level))
+    }
+}
+
+fn parse_ability(section: &Section) -> Result<Ability, DataError> {
+    let field = |key: &str| section.get(key).ok_or_else(|| section.error(format!("ability needs a {}", key)));
+
+    let cost_field = field("cost")?;
+    let cost = match cost_field.value.split_whitespace().collect::<Vec<_>>().as_slice() {
+        ["mana", n] => n.parse().ok().map(Cost::Mana),
+        ["cooldown", n] => n.parse().ok().map(Cost::Cooldown),
+        _ => None,
+    }
+    .ok_or_else(|| cost_field.error("cost should be 'mana <n>' or 'cooldown <turns>'"))?;
+
+    let mut effects = Vec::new();
+    for f in section.fields.iter().filter(|f| f.key == "effect") {
+        effects.push(parse_effect(f)?);
+    }
+    let learn = match section.get("learn") {
+        Some(f) => Some(f.value.parse().map_err(|_| f.error("learn should be a level"))?),
+        None => None,
+    };
+    if let Some(f) = section.fields.iter().find(|f| !["name", "cost", "target", "effect", "learn"].contains(&f.key.as_str())) {
+        return Err(f.error(format!("unknown key '{}'", f.key)));
+    }
+
+    Ok(Ability {
+        id: section.name.clone(),
+        name: field("name")?.value.clone(),
+        cost,
+        shape: parse_shape(field("target")?)?,
+        effects,
+        learn,
+    })
+}
+
+fn parse_shape(field: &Field) -> Result<Shape, DataError> {
+    let words: Vec<&str> = field.value.split_whitespace().collect();
+    let num = |w: &str| w.parse::<i32>().map_err(|_| field.error(format!("'{}' is not a number", w)));
+    Ok(match words.as_slice() {
+        ["self"] => Shape::Caster,
+        ["single", range] => Shape::Single { range: num(range)? },
+        ["line", range] => Shape::Line { range: num(range)? },
+        ["cone", range] => Shape::Cone { range: num(range)? },
+        ["burst", range, radius] => Shape::Burst { range: num(range)?, radius: num(radius)? },
+        _ => return Err(field.error(format!("unknown target '{}'", field.value))),
+    })
+}
+
+fn parse_effect(field: &Field) -> Result<AbilityEffect, DataError> {
+    let words: Vec<&str> = field.value.split_whitespace().collect();
+    Ok(match words.as_slice() {
+        ["damage", n] => AbilityEffect::Damage(n.parse().map_err(|_| field.error("damage needs an amount"))?),
+        ["heal", n] => AbilityEffect::Heal(n.parse().map_err(|_| field.error("heal needs an amount"))?),
+        ["apply", name, turns, potency] => {
+            let kind = StatusKind::from_name(name).ok_or_else(|| field.error("unknown status effect"))?;
+            match (turns.parse(), potency.parse()) {
+                (Ok(turns), Ok(potency)) => AbilityEffect::Apply(StatusEffect::new(kind, turns, potency)),
+                _ => return Err(field.error("apply needs turns and potency")),
+            }
+        }
+        ["teleport"] => AbilityEffect::Teleport,
+        ["terrain", "wall"] => AbilityEffect::Terrain(Tile::wall().char),
+        ["terrain", "floor"] => AbilityEffect::Terrain(Tile::floor().char),
+        _ => return Err(field.error(format!("unknown effect '{}'", field.value))),
+    })
+}
+
+/// What a caster knows, plus the mana and cooldowns that limit using it.
+#[derive(Clone, Debug, Default)]
+pub struct Spellbook {
+    /// Ability ids, in the order they were learned.
+    pub known: Vec<String>,
+    pub mana: i32,
+    pub max_mana: i32,
+    /// Turn each ability on cooldown can next be used.
+    ready_at: HashMap<String, u64>,
+    /// Turns counted towards the next point of mana.
+    regen: u32,
+}
+
+impl Spellbook {
+    pub fn new(known: Vec<String>, max_mana: i32) -> Self {
+        Self {
+            known,
+            mana: max_mana,
+            max_mana,
+            ..Self::default()
+        }
+    }
+
+    pub fn learn(&mut self, id: &str) -> bool {
+        if self.known.iter().any(|k| k == id) {
+            return false;
+        }
+        self.known.push(id.to_string());
+        true
+    }
+
+    /// Turns left before `id` comes off cooldown.
+    pub fn cooldown_left(&self, id: &str, turn: u64) -> u64 {
+        self.ready_at.get(id).map_or(0, |ready| ready.saturating_sub(turn))
+    }
+
+    /// Why `ability` can't be used on `turn`, if it can't.
+    pub fn check(&self, ability: &Ability, turn: u64) -> Result<(), String> {
+        match ability.cost {
+            Cost::Mana(n) if self.mana < n => Err(format!("You need {} mana for {}.", n, ability.name)),
+            Cost::Cooldown(_) if self.cooldown_left(&ability.id, turn) > 0 => Err(format!(
+                "{} is ready in {} turns.",
+                ability.name,
+                self.cooldown_left(&ability.id, turn)
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn pay(&mut self, ability: &Ability, turn: u64) {
+        match ability.cost {
+            Cost::Mana(n) => self.mana -= n,
+            Cost::Cooldown(n) => {
+                self.ready_at.insert(ability.id.clone(), turn + n);
+            }
+        }
+    }
+
+    /// Regains a point of mana every few of the caster's turns.
+    pub fn on_turn(&mut self) {
+        if self.mana >= self.max_mana {
+            self.regen = 0;
+            return;
+        }
+        self.regen += 1;
+        if self.regen >= MANA_REGEN_TURNS {
+            self.regen = 0;
+            self.mana += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::chunk::chunk_coords;
+
+    fn abilities(text: &str) -> Result<Abilities, DataError> {
+        Abilities::from_sections(&data::parse(text).unwrap())
+    }
+
+    /// An open room around the origin, walled off at x = 4.
+    fn room() -> MapManager {
+        let mut map = MapManager::new(0);
+        for pos in geometry::circle(Pos::new(0, 0), 8) {
+            let (cx, cy) = chunk_coords(pos);
+            map.generate_chunk_if_needed(cx, cy);
+        }
+        for pos in geometry::circle(Pos::new(0, 0), 8) {
+            map.set_tile(pos, if pos.x == 4 { Tile::wall() } else { Tile::floor() });
+        }
+        map
+    }
+
+    #[test]
+    fn test_builtin_abilities_are_valid() {
+        let all = abilities(EMBEDDED).unwrap();
+        assert!(all.learned_by(1).count() >= 1);
+        for monster in crate::monster::MONSTERS {
+            for id in monster.abilities {
+                assert!(all.get(id).is_some(), "{} knows missing ability {}", monster.id, id);
+            }
+        }
+    }
+
+    #[test]
+    fn test_rejects_bad_abilities() {
+        assert!(abilities("[ability a]\nname = a\ncost = mana\ntarget = self\n").is_err());
+        assert!(abilities("[ability a]\nname = a\ncost = mana 2\ntarget = blob 3\n").is_err());
+        assert!(abilities("[ability a]\nname = a\ncost = mana 2\ntarget = self\neffect = explode\n").is_err());
+        assert!(abilities("[ability a]\nname = a\ncost = mana 2\ntarget = self\nrange = 4\n").is_err());
+    }
+
+    #[test]
+    fn test_shapes() {
+        let map = room();
+        let origin = Pos::new(0, 0);
+        assert_eq!(Shape::Caster.area(&map, origin, Pos::new(3, 3)), vec![origin]);
+        assert!(Shape::Single { range: 2 }.area(&map, origin, Pos::new(3, 0)).is_empty());
+
+        // A line runs its full length and stops at the wall
+        let line = Shape::Line { range: 6 }.area(&map, origin, Pos::new(1, 0));
+        assert_eq!(line.last(), Some(&Pos::new(4, 0)));
+        assert_eq!(line.len(), 4);
+
+        // A cone widens away from the caster and stays on its side
+        let cone = Shape::Cone { range: 3 }.area(&map, origin, Pos::new(0, -1));
+        assert!(cone.contains(&Pos::new(0, -3)) && cone.contains(&Pos::new(1, -2)));
+        assert!(!cone.contains(&Pos::new(2, -1)));
+        assert!(cone.iter().all(|p| p.y < 0));
+
+        let burst = Shape::Burst { range: 5, radius: 1 }.area(&map, origin, Pos::new(0, 3));
+        assert_eq!(burst.len(), 5);
+    }
+
+    #[test]
+    fn test_spellbook_costs() {
+        let all = abilities(EMBEDDED).unwrap();
+        let (spark, mend) = (all.get("spark").unwrap(), all.get("mend").unwrap());
+        let mut book = Spellbook::new(vec!["spark".to_string(), "mend".to_string()], 4);
+
+        assert!(book.check(spark, 0).is_ok());
+        book.pay(spark, 0);
+        assert!(book.check(spark, 0).is_err());
+        for _ in 0..MANA_REGEN_TURNS * 2 {
+            book.on_turn();
+        }
+        assert_eq!(book.mana, 3);
+
+        book.pay(mend, 10);
+        assert!(book.check(mend, 39).is_err());
+        assert!(book.check(mend, 40).is_ok());
+    }
+}
@@ -0,0 +1,218 @@
+// ai.rs
+//
+// Monster decision making. `think` only looks at the world and returns what
+// the monster wants to do; `GameState` applies the action, so movement goes
+// through the same checks as the player's.
+use macroquad::rand;
+use crate::entity::Entity;
+use crate::geometry::{Direction, Pos};
+use crate::map::MapManager;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AiState {
+    /// Idle: shuffles around at random.
+    Wandering,
+    /// Heading for where an enemy was last seen or heard.
+    Chasing { last_seen: Pos },
+    /// Badly hurt and running away from its enemies.
+    Fleeing,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Ai {
+    pub state: AiState,
+    pub sight_radius: i32,
+    /// How keen its ears are; see `noise::reach`.
+    pub hearing_radius: i32,
+    /// Health fraction at or below which the monster runs away.
+    pub flee_threshold: f32,
+}
+
+impl Ai {
+    pub fn new(sight_radius: i32, hearing_radius: i32, flee_threshold: f32) -> Self {
+        Self {
+            state: AiState::Wandering,
+            sight_radius,
+            hearing_radius,
+            flee_threshold,
+        }
+    }
+
+    /// Whether a monster at `from` can see `to`.
+    pub fn can_see(&self, map: &MapManager, from: Pos, to: Pos) -> bool {
+        from.distance_sq(to) <= self.sight_radius * self.sight_radius && map.has_line_of_sight(from, to)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AiAction {
+    Wait,
+    Move(Direction),
+    /// Melee the enemy, which is adjacent.
+    Attack,
+}
+
+/// Decides what `entity` does this turn about `enemy`, the position of the
+/// nearest thing it would fight, if there is one. Returns its next state
+/// and action. `walkable` must apply the same rules the player moves by.
+pub fn think<F>(entity: &Entity, ai: &Ai, enemy: Option<Pos>, map: &MapManager, walkable: F) -> (AiState, AiAction)
+where
+    F: Fn(Pos) -> bool,
+{
+    let pos = entity.pos;
+    let hurt = entity.fighter.is_some_and(|f| f.health_fraction() <= ai.flee_threshold);
+    let seen = enemy.filter(|e| ai.can_see(map, pos, *e));
+
+    if hurt && (seen.is_some() || ai.state == AiState::Fleeing) {
+        // Once its enemy is out of sight the monster calms down
+        let Some(threat) = seen else { return (AiState::Wandering, AiAction::Wait) };
+        let action = step_away(pos, threat, &walkable).map_or(AiAction::Wait, AiAction::Move);
+        return (AiState::Fleeing, action);
+    }
+
+    if let Some(target) = seen {
+        let state = AiState::Chasing { last_seen: target };
+        if pos.chebyshev(target) <= 1 {
+            return (state, AiAction::Attack);
+        }
+        let action = step_towards(pos, target, &walkable).map_or(AiAction::Wait, AiAction::Move);
+        return (state, action);
+    }
+
+    match ai.state {
+        AiState::Chasing { last_seen } if last_seen != pos => {
+            match step_towards(pos, last_seen, &walkable) {
+                Some(dir) => (ai.state, AiAction::Move(dir)),
+                // Lost the trail
+                None => (AiState::Wandering, AiAction::Wait),
+            }
+        }
+        _ => (AiState::Wandering, wander(pos, &walkable)),
+    }
+}
+
+/// The walkable neighbouring step that gets closest to `target`, if any of
+/// them is an improvement.
+fn step_towards<F: Fn(Pos) -> bool>(from: Pos, target: Pos, walkable: &F) -> Option<Direction> {
+    let current = from.distance_sq(target);
+    Direction::ALL
+        .into_iter()
+        .filter(|d| walkable(from + *d))
+        .map(|d| (d, (from + d).distance_sq(target)))
+        .filter(|(_, dist)| *dist < current)
+        .min_by_key(|(_, dist)| *dist)
+        .map(|(d, _)| d)
+}
+
+/// The walkable neighbouring step that gets furthest from `threat`.
+fn step_away<F: Fn(Pos) -> bool>(from: Pos, threat: Pos, walkable: &F) -> Option<Direction> {
+    let current = from.distance_sq(threat);
+    Direction::ALL
+        .into_iter()
+        .filter(|d| walkable(from + *d))
+        .map(|d| (d, (from + d).distance_sq(threat)))
+        .filter(|(_, dist)| *dist > current)
+        .max_by_key(|(_, dist)| *dist)
+        .map(|(d, _)| d)
+}
+
+fn wander<F: Fn(Pos) -> bool>(from: Pos, walkable: &F) -> AiAction {
+    // Idle monsters spend half their turns standing still
+    if rand::gen_range(0, 2) == 0 {
+        return AiAction::Wait;
+    }
+    let dir = Direction::CARDINALS[rand::gen_range(0, 4)];
+    if walkable(from + dir) {
+        AiAction::Move(dir)
+    } else {
+        AiAction::Wait
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use macroquad::prelude::RED;
+    use crate::combat::Fighter;
+    use crate::map::chunk::Chunk;
+    use crate::map::tile::Tile;
+
+    /// A single open room spanning x 1..20, y 1..10.
+    fn room() -> MapManager {
+        let mut map = MapManager::new(0);
+        map.chunks.insert((0, 0), Chunk::new(0, 0, 0));
+        for y in 1..10 {
+            for x in 1..20 {
+                map.set_tile(Pos::new(x, y), Tile::floor());
+            }
+        }
+        map
+    }
+
+    fn monster(pos: Pos, hp: i32) -> (Entity, Ai) {
+        let mut fighter = Fighter::new(10, 3, 0, 70);
+        fighter.hp = hp;
+        let ai = Ai::new(8, 2, 0.3);
+        (Entity::new(pos, 'g', RED, "goblin").with_fighter(fighter), ai)
+    }
+
+    #[test]
+    fn test_chases_visible_player() {
+        let map = room();
+        let (goblin, ai) = monster(Pos::new(5, 5), 10);
+        let player = Pos::new(10, 5);
+        let walkable = |p: Pos| map.get_tile(p).is_walkable() && p != player;
+
+        let (state, action) = think(&goblin, &ai, Some(player), &map, walkable);
+        assert_eq!(state, AiState::Chasing { last_seen: player });
+        assert_eq!(action, AiAction::Move(Direction::East));
+    }
+
+    #[test]
+    fn test_flees_when_badly_hurt() {
+        let map = room();
+        let (goblin, ai) = monster(Pos::new(5, 5), 2);
+        let player = Pos::new(7, 5);
+        let walkable = |p: Pos| map.get_tile(p).is_walkable() && p != player;
+
+        let (state, action) = think(&goblin, &ai, Some(player), &map, walkable);
+        assert_eq!(state, AiState::Fleeing);
+        let AiAction::Move(dir) = action else { panic!("expected to move, got {:?}", action) };
+        assert!((goblin.pos + dir).distance_sq(player) > goblin.pos.distance_sq(player));
+    }
+
+    #[test]
+    fn test_ignores_player_out_of_range() {
+        let map = room();
+        let (goblin, ai) = monster(Pos::new(2, 2), 10);
+        let player = Pos::new(18, 8);
+        let walkable = |p: Pos| map.get_tile(p).is_walkable() && p != player;
+
+        let (state, _) = think(&goblin, &ai, Some(player), &map, walkable);
+        assert_eq!(state, AiState::Wandering);
+    }
+
+    #[test]
+    fn test_investigates_where_it_heard_something() {
+        let map = room();
+        let (goblin, mut ai) = monster(Pos::new(2, 2), 10);
+        ai.state = AiState::Chasing { last_seen: Pos::new(8, 2) };
+        let player = Pos::new(18, 8);
+        let walkable = |p: Pos| map.get_tile(p).is_walkable() && p != player;
+
+        let (state, action) = think(&goblin, &ai, Some(player), &map, walkable);
+        assert_eq!(state, ai.state);
+        assert_eq!(action, AiAction::Move(Direction::East));
+    }
+
+    #[test]
+    fn test_attacks_when_adjacent() {
+        let map = room();
+        let (goblin, ai) = monster(Pos::new(5, 5), 10);
+        let player = Pos::new(6, 6);
+        let walkable = |p: Pos| map.get_tile(p).is_walkable() && p != player;
+
+        let (_, action) = think(&goblin, &ai, Some(player), &map, walkable);
+        assert_eq!(action, AiAction::Attack);
+    }
+}
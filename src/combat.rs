@@ -0,0 +1,126 @@
+// combat.rs
+//
+// Melee stats and attack resolution.
+use macroquad::rand;
+use crate::status::StatusEffect;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Fighter {
+    pub hp: i32,
+    pub max_hp: i32,
+    pub attack: i32,
+    pub defense: i32,
+    /// Percent chance, 0 to 100, that an attack lands.
+    pub hit_chance: i32,
+    /// Inflicted on whoever this fighter hits, e.g. a spider's venom.
+    pub on_hit: Option<StatusEffect>,
+}
+
+impl Fighter {
+    pub fn new(max_hp: i32, attack: i32, defense: i32, hit_chance: i32) -> Self {
+        Self {
+            hp: max_hp,
+            max_hp,
+            attack,
+            defense,
+            hit_chance,
+            on_hit: None,
+        }
+    }
+
+    pub fn with_on_hit(mut self, effect: StatusEffect) -> Self {
+        self.on_hit = Some(effect);
+        self
+    }
+
+    /// Fraction of health remaining, 0.0 to 1.0.
+    pub fn health_fraction(&self) -> f32 {
+        self.hp.max(0) as f32 / self.max_hp.max(1) as f32
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.hp <= 0
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AttackResult {
+    Miss,
+    Hit { damage: i32 },
+    Kill { damage: i32 },
+}
+
+/// Rolls one melee attack and applies the damage to `defender`.
+pub fn attack(attacker: &Fighter, defender: &mut Fighter) -> AttackResult {
+    let hit_roll = rand::gen_range(0, 100);
+    let damage_roll = rand::gen_range(-1, 2);
+    resolve(attacker, defender, hit_roll, damage_roll)
+}
+
+/// Deterministic core of `attack`. `hit_roll` is 0..100 and the attack lands
+/// if it's under the attacker's hit chance; `damage_roll` is added to the
+/// attacker's attack before the defender's defense is taken off. A hit always
+/// does at least one point of damage.
+pub fn resolve(attacker: &Fighter, defender: &mut Fighter, hit_roll: i32, damage_roll: i32) -> AttackResult {
+    if hit_roll >= attacker.hit_chance {
+        return AttackResult::Miss;
+    }
+
+    let damage = (attacker.attack + damage_roll - defender.defense).max(1);
+    defender.hp -= damage;
+    if defender.is_dead() {
+        AttackResult::Kill { damage }
+    } else {
+        AttackResult::Hit { damage }
+    }
+}
+
+/// Log line for an attack, e.g. "The goblin hits you for 3."
+pub fn describe(attacker: &str, defender: &str, result: AttackResult) -> String {
+    let (miss, hit) = if attacker == "You" { ("miss", "hit") } else { ("misses", "hits") };
+    match result {
+        AttackResult::Miss => format!("{} {} {}.", attacker, miss, defender),
+        AttackResult::Hit { damage } => format!("{} {} {} for {}.", attacker, hit, defender, damage),
+        AttackResult::Kill { damage } => {
+            let victim = if defender == "you" { "you" } else { "it" };
+            format!("{} {} {} for {}, killing {}!", attacker, hit, defender, damage, victim)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hit_and_miss() {
+        let attacker = Fighter::new(10, 5, 0, 70);
+        let mut defender = Fighter::new(10, 1, 2, 70);
+
+        assert_eq!(resolve(&attacker, &mut defender, 70, 0), AttackResult::Miss);
+        assert_eq!(defender.hp, 10);
+
+        assert_eq!(resolve(&attacker, &mut defender, 69, 0), AttackResult::Hit { damage: 3 });
+        assert_eq!(defender.hp, 7);
+    }
+
+    #[test]
+    fn test_minimum_damage_and_kill() {
+        let weak = Fighter::new(10, 1, 0, 100);
+        let mut tank = Fighter::new(2, 1, 10, 100);
+
+        assert_eq!(resolve(&weak, &mut tank, 0, -1), AttackResult::Hit { damage: 1 });
+        assert_eq!(resolve(&weak, &mut tank, 0, -1), AttackResult::Kill { damage: 1 });
+        assert!(tank.is_dead());
+    }
+
+    #[test]
+    fn test_describe() {
+        let msg = describe("The rat", "you", AttackResult::Miss);
+        assert_eq!(msg, "The rat misses you.");
+        let msg = describe("You", "the rat", AttackResult::Hit { damage: 2 });
+        assert_eq!(msg, "You hit the rat for 2.");
+        let msg = describe("The orc", "you", AttackResult::Kill { damage: 9 });
+        assert_eq!(msg, "The orc hits you for 9, killing you!");
+    }
+}
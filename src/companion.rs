@@ -0,0 +1,156 @@
+// companion.rs
+//
+// The player's companions: pets and hired swords that follow them around
+// and fight at their side. Like `ai::think`, `think` only decides what a
+// companion does; `GameState` carries it out.
+use crate::ai::{self, Ai, AiAction, AiState};
+use crate::entity::{Entity, EntityId};
+use crate::geometry::{Direction, Pos};
+use crate::map::MapManager;
+
+/// A following companion stops once it's this close to the player.
+const FOLLOW_DISTANCE: i32 = 2;
+/// Following companions only break off for enemies this close to the player.
+const GUARD_RADIUS: i32 = 6;
+/// Tiles searched when pathfinding back to the player.
+const PATH_LIMIT: usize = 1500;
+
+/// What the player has told a companion to do.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Order {
+    /// Keep up with the player and fight enemies near them.
+    Follow,
+    /// Hold position, only fighting what comes next to it.
+    Stay,
+    /// Go after this enemy wherever it is.
+    Attack(EntityId),
+}
+
+impl Order {
+    pub fn describe(self) -> &'static str {
+        match self {
+            Order::Follow => "following",
+            Order::Stay => "staying put",
+            Order::Attack(_) => "attacking",
+        }
+    }
+}
+
+/// Decides what a companion does this turn under `order`. `enemy` is the
+/// position of the enemy it would fight: the target of an attack order, or
+/// the nearest one it can see. `walkable` must apply the same rules the
+/// player moves by.
+pub fn think<F>(
+    entity: &Entity,
+    ai: &Ai,
+    order: Order,
+    player_pos: Pos,
+    enemy: Option<Pos>,
+    map: &MapManager,
+    walkable: F,
+) -> (AiState, AiAction)
+where
+    F: Fn(Pos) -> bool,
+{
+    let pos = entity.pos;
+    match order {
+        Order::Attack(_) => match enemy {
+            Some(target) => go_for(pos, target, map, &walkable),
+            None => (AiState::Wandering, AiAction::Wait),
+        },
+        Order::Stay => match enemy.filter(|e| e.chebyshev(pos) <= 1) {
+            Some(target) => (AiState::Chasing { last_seen: target }, AiAction::Attack),
+            None => (AiState::Wandering, AiAction::Wait),
+        },
+        Order::Follow => {
+            if let Some(target) = enemy.filter(|e| e.chebyshev(player_pos) <= GUARD_RADIUS) {
+                return ai::think(entity, ai, Some(target), map, walkable);
+            }
+            if pos.chebyshev(player_pos) <= FOLLOW_DISTANCE {
+                return (AiState::Wandering, AiAction::Wait);
+            }
+            (AiState::Wandering, step_along_path(pos, player_pos, map, &walkable))
+        }
+    }
+}
+
+/// Closes in on `target` by the shortest path and attacks once adjacent.
+fn go_for<F: Fn(Pos) -> bool>(pos: Pos, target: Pos, map: &MapManager, walkable: &F) -> (AiState, AiAction) {
+    let state = AiState::Chasing { last_seen: target };
+    if pos.chebyshev(target) <= 1 {
+        return (state, AiAction::Attack);
+    }
+    (state, step_along_path(pos, target, map, walkable))
+}
+
+fn step_along_path<F: Fn(Pos) -> bool>(pos: Pos, to: Pos, map: &MapManager, walkable: &F) -> AiAction {
+    let next = map
+        .find_path(pos, to, PATH_LIMIT, |p, _| walkable(p))
+        .and_then(|path| path.first().copied())
+        .filter(|next| *next != to);
+    match next.and_then(|n| Direction::from_delta(n.x - pos.x, n.y - pos.y)) {
+        Some(dir) => AiAction::Move(dir),
+        None => AiAction::Wait,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use macroquad::prelude::BEIGE;
+    use crate::combat::Fighter;
+    use crate::map::chunk::Chunk;
+    use crate::map::tile::Tile;
+
+    /// Two rooms joined by a gap in the wall at (10, 8).
+    fn rooms() -> MapManager {
+        let mut map = MapManager::new(0);
+        map.chunks.insert((0, 0), Chunk::new(0, 0, 0));
+        for y in 1..10 {
+            for x in 1..20 {
+                if x != 10 || y == 8 {
+                    map.set_tile(Pos::new(x, y), Tile::floor());
+                }
+            }
+        }
+        map
+    }
+
+    fn dog(pos: Pos) -> (Entity, Ai) {
+        let fighter = Fighter::new(10, 3, 0, 70);
+        (Entity::new(pos, 'd', BEIGE, "dog").with_fighter(fighter), Ai::new(8, 4, 0.2))
+    }
+
+    #[test]
+    fn test_follows_round_walls() {
+        let map = rooms();
+        let (dog, ai) = dog(Pos::new(8, 2));
+        let player = Pos::new(12, 2);
+        let walkable = |p: Pos| map.get_tile(p).is_walkable() && p != player;
+
+        // Straight towards the player is a wall; the path goes via the gap
+        let (_, action) = think(&dog, &ai, Order::Follow, player, None, &map, walkable);
+        let AiAction::Move(dir) = action else { panic!("expected to move, got {:?}", action) };
+        assert_eq!((dog.pos + dir).y, 3);
+
+        let (_, action) = think(&dog, &ai, Order::Stay, player, None, &map, walkable);
+        assert_eq!(action, AiAction::Wait);
+    }
+
+    #[test]
+    fn test_attack_order_chases_target() {
+        let map = rooms();
+        let (dog, ai) = dog(Pos::new(2, 2));
+        let player = Pos::new(3, 3);
+        let target = Pos::new(15, 8);
+        let walkable = |p: Pos| map.get_tile(p).is_walkable() && p != player;
+
+        // Too far from the player to bother with when following...
+        let (_, action) = think(&dog, &ai, Order::Follow, player, Some(target), &map, walkable);
+        assert_eq!(action, AiAction::Wait);
+        // ...but told to, it goes after it
+        let (state, action) = think(&dog, &ai, Order::Attack(EntityId(1)), player, Some(target), &map, walkable);
+        assert_eq!(state, AiState::Chasing { last_seen: target });
+        assert!(matches!(action, AiAction::Move(_)));
+    }
+}
@@ -0,0 +1,220 @@
+// crafting.rs
+//
+// Crafting recipes, loaded from `data/recipes.txt`. A recipe turns a handful
+// of carried items into a new one.
+use crate::data::{self, DataError, Field, Section};
+use crate::item::{self, Inventory, Item};
+
+const EMBEDDED: &str = include_str!("../data/recipes.txt");
+
+#[derive(Clone, Debug)]
+pub struct Recipe {
+    pub id: String,
+    /// Item id of what it makes.
+    pub makes: &'static str,
+    /// How many it makes at once.
+    pub count: i32,
+    /// Item ids and how many of each get used up.
+    pub needs: Vec<(&'static str, i32)>,
+}
+
+impl Recipe {
+    /// Whether `inventory` holds everything the recipe uses up.
+    pub fn can_make(&self, inventory: &Inventory) -> bool {
+        self.needs
+            .iter()
+            .all(|(id, count)| inventory.items.iter().filter(|i| i.id == *id).count() as i32 >= *count)
+    }
+
+    /// Uses up the ingredients and returns what's made, for the caller to
+    /// find room for. `None`, with nothing used up, if `inventory` is short
+    /// of anything.
+    pub fn craft(&self, inventory: &mut Inventory) -> Option<Vec<Item>> {
+        if !self.can_make(inventory) {
+            return None;
+        }
+        for (id, count) in &self.needs {
+            for _ in 0..*count {
+                let index = inventory.items.iter().position(|i| i.id == *id)?;
+                inventory.remove(index);
+            }
+        }
+        Some((0..self.count).filter_map(|_| item::make(self.makes)).collect())
+    }
+
+    /// "3 darts: 1 bone, 1 iron ore"
+    pub fn describe(&self) -> String {
+        let name = |id: &str| item::template(id).map_or("?", |t| t.name);
+        let makes = match self.count {
+            1 => name(self.makes).to_string(),
+            n => format!("{} {}s", n, name(self.makes)),
+        };
+        let needs: Vec<String> = self.needs.iter().map(|(id, count)| format!("{} {}", count, name(id))).collect();
+        format!("{}: {}", makes, needs.join(", "))
+    }
+}
+
+pub struct Recipes {
+    list: Vec<Recipe>,
+}
+
+impl Recipes {
+    /// Loads `data/recipes.txt`, falling back to the built-in copy. Also
+    /// returns a warning if the file on disk had to be ignored.
+    pub fn load() -> (Self, Option<String>) {
+        data::load("recipes.txt", EMBEDDED, Self::from_sections)
+    }
+
+    pub fn from_sections(sections: &[Section]) -> Result<Self, DataError> {
+        let mut list: Vec<Recipe> = Vec::new();
+        for section in sections {
+            if section.kind != "recipe" {
+                return Err(section.error(format!("unknown section kind '{}'", section.kind)));
+            }
+            if list.iter().any(|r| r.id == section.name) {
+                return Err(section.error(format!("recipe '{}' is defined twice", section.name)));
+            }
+            list.push(parse_recipe(section)?);
+        }
+        Ok(Self { list })
+    }
+
+    /// The recipes `inventory` has the materials for, in file order.
+    pub fn available(&self, inventory: &Inventory) -> Vec<&Recipe> {
+        self.list.iter().filter(|r| r.can_make(inventory)).collect()
+    }
+}
+
+fn parse_recipe(section: &Section) -> Result<Recipe, DataError> {
+    let makes_field = section.get("makes").ok_or_else(|| section.error("recipe needs a makes"))?;
+    let (count, makes) = match makes_field.value.split_whitespace().collect::<Vec<_>>().as_slice() {
+        [id] => (1, item_id(makes_field, id)?),
+        [n, id] => (parse_count(makes_field, n)?, item_id(makes_field, id)?),
+        _ => return Err(makes_field.error("makes should be '[count] <item>'")),
+    };
+
+    let mut needs = Vec::new();
+    for field in &section.fields {
+        match field.key.as_str() {
+            "makes" => {}
+            "needs" => {
+                let (id, count) = match field.value.split_whitespace().collect::<Vec<_>>().as_slice() {
+                    [n, id] => (item_id(field, id)?, parse_count(field, n)?),
+                    _ => return Err(field.error("needs should be '<count> <item>'")),
+                };
+                // The same item on two lines adds up
+                match needs.iter_mut().find(|(need, _)| *need == id) {
+                    Some((_, total)) => *total += count,
+                    None => needs.push((id, count)),
+                }
+            }
+            key => return Err(field.error(format!("unknown key '{}'", key))),
+        }
+    }
+    if needs.is_empty() {
+        return Err(section.error("recipe needs at least one ingredient"));
+    }
+
+    Ok(Recipe {
+        id: section.name.clone(),
+        makes,
+        count,
+        needs,
+    })
+}
+
+fn parse_count(field: &Field, word: &str) -> Result<i32, DataError> {
+    word.parse()
+        .ok()
+        .filter(|n| *n > 0)
+        .ok_or_else(|| field.error(format!("'{}' is not a count", word)))
+}
+
+fn item_id(field: &Field, id: &str) -> Result<&'static str, DataError> {
+    item::template(id).map(|t| t.id).ok_or_else(|| field.error(format!("no item called '{}'", id)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quest::{Event, QuestLog, Quests};
+
+    fn recipes(text: &str) -> Result<Recipes, DataError> {
+        Recipes::from_sections(&data::parse(text).unwrap())
+    }
+
+    fn carrying(ids: &[&str]) -> Inventory {
+        let mut inventory = Inventory::new(20);
+        for id in ids {
+            inventory.add(item::make(id).unwrap()).unwrap();
+        }
+        inventory
+    }
+
+    #[test]
+    fn test_builtin_recipes_are_valid() {
+        let all = recipes(EMBEDDED).unwrap();
+        assert!(!all.list.is_empty());
+        let darts = all.list.iter().find(|r| r.id == "darts").unwrap();
+        assert_eq!(darts.describe(), "3 darts: 1 bone, 1 iron ore");
+    }
+
+    #[test]
+    fn test_rejects_bad_recipes() {
+        assert!(recipes("[recipe a]\nmakes = unicorn\nneeds = 1 bone\n").is_err());
+        assert!(recipes("[recipe a]\nmakes = torch\n").is_err());
+        assert!(recipes("[recipe a]\nmakes = torch\nneeds = 0 bone\n").is_err());
+        assert!(recipes("[recipe a]\nmakes = torch\nneeds = bone\n").is_err());
+    }
+
+    #[test]
+    fn test_only_makeable_recipes_are_available() {
+        let all = recipes(
+            "[recipe potion]\nmakes = healing_potion\nneeds = 2 herb\n\
+             [recipe torch]\nmakes = torch\nneeds = 1 bone\nneeds = 1 spider_silk\n",
+        )
+        .unwrap();
+        let ids = |inventory: &Inventory| all.available(inventory).iter().map(|r| r.id.clone()).collect::<Vec<_>>();
+        assert!(ids(&carrying(&["herb", "bone"])).is_empty());
+        assert_eq!(ids(&carrying(&["herb", "bone", "herb"])), vec!["potion"]);
+        assert_eq!(ids(&carrying(&["herb", "bone", "herb", "spider_silk"])), vec!["potion", "torch"]);
+    }
+
+    #[test]
+    fn test_repeated_ingredients_add_up() {
+        let all = recipes("[recipe potion]\nmakes = healing_potion\nneeds = 1 herb\nneeds = 1 herb\n").unwrap();
+        let potion = &all.list[0];
+        assert_eq!(potion.needs, vec![("herb", 2)]);
+
+        let mut inventory = carrying(&["herb", "bone"]);
+        assert!(!potion.can_make(&inventory));
+        assert!(potion.craft(&mut inventory).is_none());
+        assert_eq!(inventory.items.len(), 2);
+
+        inventory.add(item::make("herb").unwrap()).unwrap();
+        assert_eq!(potion.craft(&mut inventory).unwrap().len(), 1);
+        assert_eq!(inventory.items.len(), 1);
+    }
+
+    #[test]
+    fn test_crafting_counts_towards_fetch_quests() {
+        let all = recipes("[recipe darts]\nmakes = 3 dart\nneeds = 1 bone\nneeds = 1 iron_ore\n").unwrap();
+        let quests = Quests::from_sections(
+            &data::parse("[quest d]\nname = D\ntext = D\ngoal = fetch 3 dart\n").unwrap(),
+        )
+        .unwrap();
+        let mut log = QuestLog::default();
+        log.start("d");
+
+        let mut inventory = carrying(&["bone", "herb", "iron_ore"]);
+        for dart in all.list[0].craft(&mut inventory).unwrap() {
+            inventory.add(dart).unwrap();
+        }
+        let ids: Vec<&str> = inventory.items.iter().map(|i| i.id).collect();
+        assert_eq!(ids, vec!["herb", "dart", "dart", "dart"]);
+
+        // What end_player_turn reports after the craft
+        let darts = inventory.items.iter().filter(|i| i.id == "dart").count() as i32;
+        assert_eq!(log.on_event(&quests, Event::Carrying("dart", darts)), vec!["d"]);
+    }
+}
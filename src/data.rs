@@ -0,0 +1,229 @@
+// data.rs
+//
+// Loader for the plain-text data files in `data/`. A file is a list of
+// sections, each a header line `[kind name]` followed by `key = value`
+// lines. Keys may repeat; blank lines and lines starting with `#` are
+// ignored. There are no trailing comments, since `#` is a perfectly good
+// character in a value (it's a wall tile in a saved chunk).
+//
+// The game reads the files from disk at start-up so they can be tweaked
+// without rebuilding, and falls back to the copy compiled into the binary
+// when a file is missing or broken.
+use std::fmt;
+use std::fs;
+use macroquad::prelude::*;
+
+/// Directory the data files are read from, relative to the working directory.
+const DATA_DIR: &str = "data";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DataError {
+    /// 1-based line number.
+    pub line: usize,
+    pub message: String,
+}
+
+impl DataError {
+    pub fn new(line: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for DataError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Field {
+    pub key: String,
+    pub value: String,
+    pub line: usize,
+}
+
+impl Field {
+    /// An error pointing at this field's line.
+    pub fn error(&self, message: impl Into<String>) -> DataError {
+        DataError::new(self.line, message)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Section {
+    pub kind: String,
+    pub name: String,
+    pub line: usize,
+    pub fields: Vec<Field>,
+}
+
+impl Section {
+    /// The first field with this key.
+    pub fn get(&self, key: &str) -> Option<&Field> {
+        self.fields.iter().find(|f| f.key == key)
+    }
+
+    /// An error pointing at this section's header.
+    pub fn error(&self, message: impl Into<String>) -> DataError {
+        DataError::new(self.line, message)
+    }
+}
+
+pub fn parse(text: &str) -> Result<Vec<Section>, DataError> {
+    let mut sections: Vec<Section> = Vec::new();
+
+    for (i, raw) in text.lines().enumerate() {
+        let line = i + 1;
+        let content = raw.trim();
+        if content.is_empty() || content.starts_with('#') {
+            continue;
+        }
+
+        if let Some(header) = content.strip_prefix('[') {
+            let header = header
+                .strip_suffix(']')
+                .ok_or_else(|| DataError::new(line, "section header is missing its closing ']'"))?;
+            let mut words = header.split_whitespace();
+            let (Some(kind), Some(name), None) = (words.next(), words.next(), words.next()) else {
+                return Err(DataError::new(line, "section header should be [kind name]"));
+            };
+            sections.push(Section {
+                kind: kind.to_string(),
+                name: name.to_string(),
+                line,
+                fields: Vec::new(),
+            });
+            continue;
+        }
+
+        let Some((key, value)) = content.split_once('=') else {
+            return Err(DataError::new(line, "expected 'key = value'"));
+        };
+        let Some(section) = sections.last_mut() else {
+            return Err(DataError::new(line, "field outside of any section"));
+        };
+        section.fields.push(Field {
+            key: key.trim().to_string(),
+            value: value.trim().to_string(),
+            line,
+        });
+    }
+
+    Ok(sections)
+}
+
+/// Reads and parses `data/<file>`, falling back to `embedded` (the copy built
+/// into the binary) if the file can't be read or doesn't parse. The second
+/// value describes what went wrong with the file on disk, if anything.
+pub fn load<T>(
+    file: &str,
+    embedded: &str,
+    build: impl Fn(&[Section]) -> Result<T, DataError>,
+) -> (T, Option<String>) {
+    let from_disk = fs::read_to_string(format!("{}/{}", DATA_DIR, file))
+        .ok()
+        .map(|text| parse(&text).and_then(|sections| build(&sections)));
+
+    let warning = match from_disk {
+        Some(Ok(data)) => return (data, None),
+        Some(Err(e)) => Some(format!("{}: {}; using built-in data.", file, e)),
+        None => None,
+    };
+
+    let data = parse(embedded)
+        .and_then(|sections| build(&sections))
+        .unwrap_or_else(|e| panic!("built-in {} is invalid: {}", file, e));
+    (data, warning)
+}
+
+/// Parses an inclusive range like `2-5`, `3-` (no upper bound), `-4` (no
+/// lower bound) or a single number.
+pub fn parse_range(text: &str) -> Option<(i32, i32)> {
+    let Some((low, high)) = text.split_once('-') else {
+        let n = text.trim().parse().ok()?;
+        return Some((n, n));
+    };
+    let low = match low.trim() {
+        "" => i32::MIN,
+        s => s.parse().ok()?,
+    };
+    let high = match high.trim() {
+        "" => i32::MAX,
+        s => s.parse().ok()?,
+    };
+    (low <= high).then_some((low, high))
+}
+
+/// Parses a colour: one of a few names, or `r g b` with each part 0 to 1.
+pub fn parse_color(text: &str) -> Option<Color> {
+    let named = match text {
+        "white" => WHITE,
+        "gray" => GRAY,
+        "lightgray" => LIGHTGRAY,
+        "yellow" => YELLOW,
+        "gold" => GOLD,
+        "orange" => ORANGE,
+        "red" => RED,
+        "pink" => PINK,
+        "magenta" => MAGENTA,
+        "purple" => PURPLE,
+        "violet" => VIOLET,
+        "blue" => BLUE,
+        "skyblue" => SKYBLUE,
+        "green" => GREEN,
+        "lime" => LIME,
+        "brown" => BROWN,
+        "beige" => BEIGE,
+        _ => {
+            let parts: Vec<f32> = text.split_whitespace().map(|p| p.parse().ok()).collect::<Option<_>>()?;
+            let [r, g, b] = parts[..] else { return None };
+            return Some(Color::new(r, g, b, 1.0));
+        }
+    };
+    Some(named)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sections() {
+        let text = "# comment\n[table loot]\nrolls = 1-2\nitem = 3 dagger\n  # indented comment\n\n[table other]\n";
+        let sections = parse(text).unwrap();
+        assert_eq!(sections.len(), 2);
+        assert_eq!((sections[0].kind.as_str(), sections[0].name.as_str()), ("table", "loot"));
+        assert_eq!(sections[0].get("rolls").map(|f| f.value.as_str()), Some("1-2"));
+        assert_eq!(sections[0].get("item").map(|f| (f.value.as_str(), f.line)), Some(("3 dagger", 4)));
+        assert_eq!(sections[0].fields.len(), 2);
+        assert!(sections[1].fields.is_empty());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(parse("rolls = 1").unwrap_err().line, 1);
+        assert_eq!(parse("[table loot]\nno equals sign").unwrap_err().line, 2);
+        assert!(parse("[table]").is_err());
+    }
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(parse_range("2-5"), Some((2, 5)));
+        assert_eq!(parse_range("3-"), Some((3, i32::MAX)));
+        assert_eq!(parse_range("-4"), Some((i32::MIN, 4)));
+        assert_eq!(parse_range("7"), Some((7, 7)));
+        assert_eq!(parse_range("5-2"), None);
+        assert_eq!(parse_range("x"), None);
+    }
+
+    #[test]
+    fn test_parse_color() {
+        assert_eq!(parse_color("gold"), Some(GOLD));
+        assert_eq!(parse_color("0.5 0.25 1"), Some(Color::new(0.5, 0.25, 1.0, 1.0)));
+        assert_eq!(parse_color("0.5 0.25"), None);
+        assert_eq!(parse_color("mauve"), None);
+    }
+}
@@ -0,0 +1,340 @@
+// dialogue.rs
+//
+// Friendly NPCs and their branching conversations, loaded from
+// `data/dialogue.txt`. A conversation is a graph of nodes; each node shows
+// some text and offers choices, and choices can be hidden behind conditions
+// on the player and change the player's state when picked.
+use std::collections::HashMap;
+use macroquad::prelude::Color;
+use crate::data::{self, DataError, Field, Section};
+use crate::entity::Player;
+use crate::item;
+use crate::monster;
+use crate::quest::Stage;
+use crate::status::{StatusEffect, StatusKind};
+
+const EMBEDDED: &str = include_str!("../data/dialogue.txt");
+/// Choice target that closes the conversation.
+pub const END: &str = "end";
+
+/// Something about the player a choice can depend on.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Condition {
+    /// Carries at least one of this item.
+    Has(&'static str),
+    /// Carries none of this item.
+    Lacks(&'static str),
+    /// A story flag is set.
+    Flag(String),
+    /// A story flag is not set.
+    Unset(String),
+    /// Below full health.
+    Hurt,
+    /// Has at least this much gold.
+    Gold(i32),
+    /// The player has got this far with a quest.
+    Quest(String, Stage),
+}
+
+impl Condition {
+    pub fn holds(&self, player: &Player) -> bool {
+        let carries = |id: &str| player.inventory.items.iter().any(|i| i.id == id);
+        match self {
+            Condition::Has(id) => carries(id),
+            Condition::Lacks(id) => !carries(id),
+            Condition::Flag(flag) => player.flags.contains(flag),
+            Condition::Unset(flag) => !player.flags.contains(flag),
+            Condition::Hurt => player.fighter.hp < player.fighter.max_hp,
+            Condition::Gold(amount) => player.gold >= *amount,
+            Condition::Quest(id, stage) => player.quests.stage(id) == *stage,
+        }
+    }
+}
+
+/// A change to the player's state.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Effect {
+    Give(&'static str),
+    Take(&'static str),
+    Heal(i32),
+    Set(String),
+    Clear(String),
+    Apply(StatusEffect),
+    /// Opens the speaker's shop.
+    Trade,
+    /// Takes this much gold.
+    Pay(i32),
+    /// The speaker becomes a companion: this monster, in their place.
+    Join(&'static str),
+    /// Gives the player a quest.
+    Quest(String),
+}
+
+#[derive(Clone, Debug)]
+pub struct Choice {
+    pub label: String,
+    /// Node to go to, or `END`.
+    pub target: String,
+    pub conditions: Vec<Condition>,
+    pub effects: Vec<Effect>,
+}
+
+impl Choice {
+    /// Whether the conditions hold and the player can afford what it costs.
+    pub fn available(&self, player: &Player) -> bool {
+        let cost: i32 = self.effects.iter().map(|e| if let Effect::Pay(n) = e { *n } else { 0 }).sum();
+        player.gold >= cost && self.conditions.iter().all(|c| c.holds(player))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Node {
+    /// Paragraphs of text.
+    pub text: Vec<String>,
+    /// Applied every time the node is shown.
+    pub effects: Vec<Effect>,
+    pub choices: Vec<Choice>,
+}
+
+impl Node {
+    /// The choices the player may currently pick, in file order.
+    pub fn available_choices<'a>(&'a self, player: &'a Player) -> impl Iterator<Item = &'a Choice> {
+        self.choices.iter().filter(|c| c.available(player))
+    }
+}
+
+/// A kind of NPC that can turn up in new chunks.
+#[derive(Clone, Debug)]
+pub struct NpcTemplate {
+    pub name: String,
+    pub glyph: char,
+    pub color: Color,
+    /// Node the conversation starts at.
+    pub talk: String,
+    /// Percent chance of appearing in each new chunk.
+    pub chance: i32,
+    /// Loot table a merchant's stock is rolled from.
+    pub shop: Option<String>,
+}
+
+pub struct Dialogues {
+    pub nodes: HashMap<String, Node>,
+    pub npcs: Vec<NpcTemplate>,
+}
+
+impl Dialogues {
+    /// Loads `data/dialogue.txt`, falling back to the built-in copy. Also
+    /// returns a warning if the file on disk had to be ignored.
+    pub fn load() -> (Self, Option<String>) {
+        data::load("dialogue.txt", EMBEDDED, Self::from_sections)
+    }
+
+    pub fn from_sections(sections: &[Section]) -> Result<Self, DataError> {
+        let mut nodes = HashMap::new();
+        let mut npcs = Vec::new();
+        for section in sections {
+            match section.kind.as_str() {
+                "node" => {
+                    if nodes.insert(section.name.clone(), parse_node(section)?).is_some() {
+                        return Err(section.error(format!("node '{}' is defined twice", section.name)));
+                    }
+                }
+                "npc" => npcs.push(parse_npc(section)?),
+                kind => return Err(section.error(format!("unknown section kind '{}'", kind))),
+            }
+        }
+
+        // Every link has to lead somewhere
+        let exists = |target: &str| target == END || nodes.contains_key(target);
+        for section in sections {
+            for field in &section.fields {
+                let target = match field.key.as_str() {
+                    "choice" => field.value.rsplit_once("->").map(|(_, t)| t.trim()),
+                    "talk" => Some(field.value.as_str()),
+                    _ => None,
+                };
+                if let Some(target) = target
+                    && !exists(target)
+                {
+                    return Err(field.error(format!("no node called '{}'", target)));
+                }
+            }
+        }
+
+        Ok(Self { nodes, npcs })
+    }
+
+    pub fn node(&self, id: &str) -> Option<&Node> {
+        self.nodes.get(id)
+    }
+}
+
+fn parse_npc(section: &Section) -> Result<NpcTemplate, DataError> {
+    let field = |key: &str| section.get(key).ok_or_else(|| section.error(format!("npc needs a {}", key)));
+
+    let glyph_field = field("glyph")?;
+    let mut chars = glyph_field.value.chars();
+    let (Some(glyph), None) = (chars.next(), chars.next()) else {
+        return Err(glyph_field.error("glyph should be a single character"));
+    };
+    let color_field = field("color")?;
+    let color = data::parse_color(&color_field.value).ok_or_else(|| color_field.error("unknown colour"))?;
+    let chance = match section.get("chance") {
+        Some(f) => f.value.parse().map_err(|_| f.error("chance should be a percentage"))?,
+        None => 0,
+    };
+
+    Ok(NpcTemplate {
+        name: field("name")?.value.clone(),
+        glyph,
+        color,
+        talk: field("talk")?.value.clone(),
+        chance,
+        shop: section.get("shop").map(|f| f.value.clone()),
+    })
+}
+
+/// `text`, `do` and `choice` lines, in order. `if` and `do` lines after a
+/// `choice` belong to that choice; `do` lines before the first choice belong
+/// to the node itself.
+fn parse_node(section: &Section) -> Result<Node, DataError> {
+    let mut node = Node {
+        text: Vec::new(),
+        effects: Vec::new(),
+        choices: Vec::new(),
+    };
+    for field in &section.fields {
+        match field.key.as_str() {
+            "text" => node.text.push(field.value.clone()),
+            "choice" => {
+                let Some((label, target)) = field.value.rsplit_once("->") else {
+                    return Err(field.error("choice should be '<label> -> <node>'"));
+                };
+                node.choices.push(Choice {
+                    label: label.trim().to_string(),
+                    target: target.trim().to_string(),
+                    conditions: Vec::new(),
+                    effects: Vec::new(),
+                });
+            }
+            "if" => {
+                let condition = parse_condition(field)?;
+                let Some(choice) = node.choices.last_mut() else {
+                    return Err(field.error("'if' has to follow a choice"));
+                };
+                choice.conditions.push(condition);
+            }
+            "do" => {
+                let effect = parse_effect(field)?;
+                match node.choices.last_mut() {
+                    Some(choice) => choice.effects.push(effect),
+                    None => node.effects.push(effect),
+                }
+            }
+            key => return Err(field.error(format!("unknown key '{}'", key))),
+        }
+    }
+    Ok(node)
+}
+
+fn item_id(field: &Field, id: &str) -> Result<&'static str, DataError> {
+    item::template(id).map(|t| t.id).ok_or_else(|| field.error(format!("no item called '{}'", id)))
+}
+
+fn parse_condition(field: &Field) -> Result<Condition, DataError> {
+    let words: Vec<&str> = field.value.split_whitespace().collect();
+    Ok(match words.as_slice() {
+        ["has", id] => Condition::Has(item_id(field, id)?),
+        ["lacks", id] => Condition::Lacks(item_id(field, id)?),
+        ["flag", flag] => Condition::Flag(flag.to_string()),
+        ["unset", flag] => Condition::Unset(flag.to_string()),
+        ["hurt"] => Condition::Hurt,
+        ["gold", n] => Condition::Gold(n.parse().map_err(|_| field.error("gold needs an amount"))?),
+        ["quest", id, stage] => Condition::Quest(
+            id.to_string(),
+            Stage::from_name(stage).ok_or_else(|| field.error("quest stage should be new, active or done"))?,
+        ),
+        _ => return Err(field.error(format!("unknown condition '{}'", field.value))),
+    })
+}
+
+fn parse_effect(field: &Field) -> Result<Effect, DataError> {
+    let words: Vec<&str> = field.value.split_whitespace().collect();
+    Ok(match words.as_slice() {
+        ["give", id] => Effect::Give(item_id(field, id)?),
+        ["take", id] => Effect::Take(item_id(field, id)?),
+        ["heal", n] => Effect::Heal(n.parse().map_err(|_| field.error("heal needs an amount"))?),
+        ["set", flag] => Effect::Set(flag.to_string()),
+        ["clear", flag] => Effect::Clear(flag.to_string()),
+        ["apply", name, turns, potency] => {
+            let kind = StatusKind::from_name(name).ok_or_else(|| field.error("unknown status effect"))?;
+            match (turns.parse(), potency.parse()) {
+                (Ok(turns), Ok(potency)) => Effect::Apply(StatusEffect::new(kind, turns, potency)),
+                _ => return Err(field.error("apply needs turns and potency")),
+            }
+        }
+        ["trade"] => Effect::Trade,
+        ["pay", n] => Effect::Pay(n.parse().map_err(|_| field.error("pay needs an amount"))?),
+        ["join", id] => Effect::Join(
+            monster::template(id)
+                .map(|t| t.id)
+                .ok_or_else(|| field.error(format!("no monster called '{}'", id)))?,
+        ),
+        ["quest", id] => Effect::Quest(id.to_string()),
+        _ => return Err(field.error(format!("unknown effect '{}'", field.value))),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dialogues(text: &str) -> Result<Dialogues, DataError> {
+        Dialogues::from_sections(&data::parse(text).unwrap())
+    }
+
+    #[test]
+    fn test_builtin_dialogue_is_valid() {
+        let d = dialogues(EMBEDDED).unwrap();
+        assert!(!d.npcs.is_empty());
+        assert!(d.npcs.iter().all(|npc| d.node(&npc.talk).is_some()));
+    }
+
+    #[test]
+    fn test_conditions_hide_choices() {
+        let d = dialogues(
+            "[node a]\ntext = Hello.\nchoice = Here, a bandage -> b\nif = has bandage\ndo = take bandage\n\
+             choice = Bye -> end\n[node b]\ndo = set helped\ntext = Thanks!\n",
+        )
+        .unwrap();
+        let node = d.node("a").unwrap();
+        let mut player = Player::new(0, 0);
+        let labels = |p: &Player| node.available_choices(p).map(|c| c.label.clone()).collect::<Vec<_>>();
+        assert_eq!(labels(&player), vec!["Bye"]);
+
+        player.inventory.add(item::make("bandage").unwrap()).unwrap();
+        assert_eq!(labels(&player), vec!["Here, a bandage", "Bye"]);
+        assert_eq!(node.choices[0].effects, vec![Effect::Take("bandage")]);
+        assert_eq!(d.node("b").unwrap().effects, vec![Effect::Set("helped".to_string())]);
+    }
+
+    #[test]
+    fn test_unaffordable_choices_are_hidden() {
+        let d = dialogues("[node a]\ntext = Toll.\nchoice = Pay -> end\ndo = pay 10\nchoice = Bye -> end\n").unwrap();
+        let node = d.node("a").unwrap();
+        let mut player = Player::new(0, 0);
+        player.gold = 9;
+        assert_eq!(node.available_choices(&player).count(), 1);
+        player.gold = 10;
+        assert_eq!(node.available_choices(&player).count(), 2);
+    }
+
+    #[test]
+    fn test_rejects_broken_links() {
+        assert!(dialogues("[node a]\nchoice = Go -> nowhere\n").is_err());
+        assert!(dialogues("[npc x]\nname = x\nglyph = x\ncolor = red\ntalk = missing\n").is_err());
+        assert!(dialogues("[node a]\nif = has bandage\n").is_err());
+        assert!(dialogues("[node a]\nchoice = Go -> end\nif = has unicorn\n").is_err());
+        assert!(dialogues("[node a]\nchoice = Join me -> end\ndo = join unicorn\n").is_err());
+    }
+}
@@ -0,0 +1,291 @@
+// entity/mod.rs
+pub mod save;
+pub mod store;
+#[cfg(test)]
+mod tests;
+
+use std::collections::{HashMap, HashSet};
+use macroquad::prelude::Color;
+use crate::ability::Spellbook;
+use crate::ai::Ai;
+use crate::combat::Fighter;
+use crate::companion::Order;
+use crate::equipment::Equipment;
+use crate::geometry::Pos;
+use crate::hunger::Hunger;
+use crate::item::{Inventory, Item};
+use crate::noise::Noise;
+use crate::progression::{Attribute, Attributes, Experience};
+use crate::quest::QuestLog;
+use crate::shop::Shop;
+use crate::status::StatusEffects;
+use crate::turn::{Energy, NORMAL_SPEED};
+
+pub use store::EntityStore;
+
+/// Number of items the player can carry.
+pub const INVENTORY_CAPACITY: usize = 20;
+/// How far the player sees with no equipment.
+pub const BASE_FOV_RADIUS: i32 = 6;
+
+pub struct Player {
+    pub pos: Pos,
+    pub color: Color,
+    pub energy: Energy,
+    pub fighter: Fighter,
+    pub inventory: Inventory,
+    pub equipment: Equipment,
+    pub effects: StatusEffects,
+    /// Story flags set and checked by dialogue.
+    pub flags: HashSet<String>,
+    pub gold: i32,
+    pub attributes: Attributes,
+    pub experience: Experience,
+    /// Chunks the player has set foot in.
+    pub visited: HashSet<(i32, i32)>,
+    pub spellbook: Spellbook,
+    pub hunger: Hunger,
+    /// Standing with each faction, by faction id.
+    pub reputation: HashMap<String, i32>,
+    pub quests: QuestLog,
+}
+
+impl Player {
+    pub fn new(x: i32, y: i32) -> Self {
+        let attributes = Attributes::new();
+        let stats = attributes.derive(1);
+        Self {
+            pos: Pos::new(x, y),
+            color: macroquad::prelude::GREEN,
+            energy: Energy::new(NORMAL_SPEED),
+            fighter: Fighter::new(stats.max_hp, stats.attack, stats.defense, stats.hit_chance),
+            inventory: Inventory::new(INVENTORY_CAPACITY),
+            equipment: Equipment::new(),
+            effects: StatusEffects::new(),
+            flags: HashSet::new(),
+            gold: 0,
+            attributes,
+            experience: Experience::new(),
+            visited: HashSet::new(),
+            spellbook: Spellbook::new(Vec::new(), stats.max_mana),
+            hunger: Hunger::new(),
+            reputation: HashMap::new(),
+            quests: QuestLog::default(),
+        }
+    }
+
+    /// Adds XP, recalculating stats if it was enough to level up. Returns
+    /// the number of levels gained.
+    pub fn gain_xp(&mut self, amount: i32) -> i32 {
+        let levels = self.experience.gain(amount);
+        if levels > 0 {
+            self.refresh_stats();
+        }
+        levels
+    }
+
+    /// Spends an unspent attribute point, if there is one.
+    pub fn raise(&mut self, attribute: Attribute) -> bool {
+        if self.experience.unspent <= 0 {
+            return false;
+        }
+        self.experience.unspent -= 1;
+        self.attributes.raise(attribute);
+        self.refresh_stats();
+        true
+    }
+
+    /// Rederives combat stats from attributes and level. Any extra max HP
+    /// comes with the HP to fill it.
+    fn refresh_stats(&mut self) {
+        let stats = self.attributes.derive(self.experience.level);
+        let fighter = &mut self.fighter;
+        fighter.hp += (stats.max_hp - fighter.max_hp).max(0);
+        fighter.max_hp = stats.max_hp;
+        fighter.attack = stats.attack;
+        fighter.defense = stats.defense;
+        fighter.hit_chance = stats.hit_chance;
+        let book = &mut self.spellbook;
+        book.mana += (stats.max_mana - book.max_mana).max(0);
+        book.max_mana = stats.max_mana;
+    }
+
+    /// Combat stats with equipment bonuses and hunger penalties applied.
+    /// `hp` is the live value.
+    pub fn combat_stats(&self) -> Fighter {
+        let mods = self.equipment.modifiers() + self.hunger.state().modifiers();
+        Fighter {
+            attack: self.fighter.attack + mods.attack,
+            defense: self.fighter.defense + mods.defense,
+            ..self.fighter
+        }
+    }
+
+    pub fn fov_radius(&self) -> i32 {
+        let bonus = self.attributes.derive(self.experience.level).sight_bonus;
+        BASE_FOV_RADIUS + bonus + self.equipment.modifiers().fov_radius
+    }
+
+    pub fn stealth(&self) -> i32 {
+        self.attributes.derive(self.experience.level).stealth
+    }
+
+    pub fn search(&self) -> i32 {
+        self.attributes.derive(self.experience.level).search
+    }
+
+    /// How far the player's footsteps carry; stealth softens them.
+    pub fn footstep_loudness(&self) -> i32 {
+        (Noise::Footsteps.loudness() - self.stealth()).max(1)
+    }
+
+    pub fn light_radius(&self) -> i32 {
+        self.equipment.modifiers().light_radius
+    }
+
+    /// How far the player can see: their own FOV pushed out by any light
+    /// they carry, cut short by blindness.
+    pub fn sight_radius(&self) -> i32 {
+        self.effects.adjust_sight((self.fov_radius() + self.light_radius()).max(1))
+    }
+}
+
+/// Stable handle to an entity. Ids are never reused, so a stale id simply
+/// stops resolving once its entity is gone.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+pub struct EntityId(pub u32);
+
+/// Anything in the world other than the player: monsters, items, NPCs.
+#[derive(Clone, Debug)]
+pub struct Entity {
+    pub id: EntityId,
+    pub pos: Pos,
+    pub glyph: char,
+    pub color: Color,
+    pub name: String,
+    /// Id of the monster template this was created from, if any.
+    pub template: Option<&'static str>,
+    /// Level a monster was created at, which scaled its stats and XP.
+    pub level: i32,
+    pub blocks_movement: bool,
+    pub fighter: Option<Fighter>,
+    pub ai: Option<Ai>,
+    /// Present on anything that takes turns.
+    pub energy: Option<Energy>,
+    /// Present on items lying on the floor.
+    pub item: Option<Item>,
+    /// Present on NPCs you can talk to: the node their conversation starts at.
+    pub dialogue: Option<String>,
+    /// Present on merchants.
+    pub shop: Option<Shop>,
+    /// Present on monsters with abilities.
+    pub spellbook: Option<Spellbook>,
+    /// Id of the faction it belongs to, which decides who it fights.
+    pub faction: Option<String>,
+    /// Present on the player's companions: what they've been told to do.
+    pub companion: Option<Order>,
+    pub effects: StatusEffects,
+}
+
+impl Entity {
+    /// A non-blocking entity. The id is assigned when it's spawned into an
+    /// `EntityStore`.
+    pub fn new(pos: Pos, glyph: char, color: Color, name: &str) -> Self {
+        Self {
+            id: EntityId(0),
+            pos,
+            glyph,
+            color,
+            name: name.to_string(),
+            template: None,
+            level: 1,
+            blocks_movement: false,
+            fighter: None,
+            ai: None,
+            energy: None,
+            item: None,
+            dialogue: None,
+            shop: None,
+            spellbook: None,
+            faction: None,
+            companion: None,
+            effects: StatusEffects::new(),
+        }
+    }
+
+    pub fn with_template(mut self, id: &'static str) -> Self {
+        self.template = Some(id);
+        self
+    }
+
+    pub fn blocking(mut self) -> Self {
+        self.blocks_movement = true;
+        self
+    }
+
+    pub fn with_fighter(mut self, fighter: Fighter) -> Self {
+        self.fighter = Some(fighter);
+        self
+    }
+
+    pub fn with_ai(mut self, ai: Ai) -> Self {
+        self.ai = Some(ai);
+        self
+    }
+
+    pub fn with_speed(mut self, speed: i32) -> Self {
+        self.energy = Some(Energy::new(speed));
+        self
+    }
+
+    pub fn with_dialogue(mut self, node: &str) -> Self {
+        self.dialogue = Some(node.to_string());
+        self
+    }
+
+    pub fn with_shop(mut self, shop: Shop) -> Self {
+        self.shop = Some(shop);
+        self
+    }
+
+    pub fn with_spellbook(mut self, spellbook: Spellbook) -> Self {
+        self.spellbook = Some(spellbook);
+        self
+    }
+
+    pub fn with_faction(mut self, faction: &str) -> Self {
+        self.faction = Some(faction.to_string());
+        self
+    }
+
+    /// Makes it one of the player's companions, following them.
+    pub fn with_companion(mut self) -> Self {
+        self.companion = Some(Order::Follow);
+        self
+    }
+
+    pub fn with_item(mut self, item: Item) -> Self {
+        self.item = Some(item);
+        self
+    }
+
+    /// Something that fights and thinks. Whether it's an enemy depends on
+    /// its faction.
+    pub fn is_creature(&self) -> bool {
+        self.fighter.is_some() && self.ai.is_some()
+    }
+
+    /// Turns a dead creature into the corpse it leaves behind.
+    pub fn make_corpse(&mut self) {
+        self.name = format!("{} corpse", self.name);
+        self.glyph = '%';
+        self.color = macroquad::prelude::MAROON;
+        self.blocks_movement = false;
+        self.fighter = None;
+        self.ai = None;
+        self.energy = None;
+        self.spellbook = None;
+        self.companion = None;
+        self.effects = StatusEffects::new();
+    }
+}
@@ -0,0 +1,206 @@
+// save.rs
+//
+// Text form of the entities stored with an unloaded chunk, in the same
+// section format as the data files. Monsters, corpses and items are written
+// as their template id plus whatever has changed since they were created;
+// NPCs come from data files, so they're written out in full, along with a
+// merchant's stock.
+use std::fmt::Write;
+use crate::ai::AiState;
+use crate::data::{self, DataError, Field, Section};
+use crate::entity::Entity;
+use crate::geometry::Pos;
+use crate::item::{self, ItemKind};
+use crate::monster;
+use crate::shop::Shop;
+use crate::status::{StatusEffect, StatusKind};
+
+/// Appends `entity` as a section. Fails, writing nothing, for an entity
+/// `read_entity` couldn't rebuild.
+pub fn write_entity(entity: &Entity, out: &mut String) -> Result<(), String> {
+    if let Some(node) = &entity.dialogue {
+        let c = entity.color;
+        let _ = writeln!(out, "[npc {}]", node);
+        let _ = writeln!(out, "pos = {} {}", entity.pos.x, entity.pos.y);
+        let _ = writeln!(out, "name = {}", entity.name);
+        let _ = writeln!(out, "glyph = {}", entity.glyph);
+        let _ = writeln!(out, "color = {} {} {}", c.r, c.g, c.b);
+        if let Some(shop) = &entity.shop {
+            let _ = writeln!(out, "shop = {}", shop.table);
+            let _ = writeln!(out, "restocked = {}", shop.restocked_at);
+            for item in &shop.stock {
+                let _ = writeln!(out, "stock = {}", item.id);
+            }
+        }
+        return Ok(());
+    }
+
+    let header = match (&entity.item, entity.template, &entity.fighter) {
+        (Some(item), _, _) => format!("item {}", item.id),
+        (None, Some(template), Some(_)) => format!("monster {}", template),
+        (None, Some(template), None) => format!("corpse {}", template),
+        (None, None, _) => return Err(format!("The {} can't be saved and is lost.", entity.name)),
+    };
+
+    let _ = writeln!(out, "[{}]", header);
+    let _ = writeln!(out, "pos = {} {}", entity.pos.x, entity.pos.y);
+    if entity.template.is_some() && entity.level != 1 {
+        let _ = writeln!(out, "level = {}", entity.level);
+    }
+    if let Some(f) = entity.fighter {
+        let _ = writeln!(out, "fighter = {} {} {} {} {}", f.hp, f.max_hp, f.attack, f.defense, f.hit_chance);
+    }
+    if let Some(energy) = entity.energy {
+        let _ = writeln!(out, "energy = {}", energy.energy);
+    }
+    if let Some(book) = &entity.spellbook {
+        let _ = writeln!(out, "mana = {}", book.mana);
+    }
+    if let Some(ItemKind::Gold(amount)) = entity.item.as_ref().map(|i| i.kind) {
+        let _ = writeln!(out, "amount = {}", amount);
+    }
+    if let Some(faction) = &entity.faction {
+        let _ = writeln!(out, "faction = {}", faction);
+    }
+    if let Some(ai) = entity.ai {
+        let state = match ai.state {
+            AiState::Wandering => "wandering".to_string(),
+            AiState::Chasing { last_seen } => format!("chasing {} {}", last_seen.x, last_seen.y),
+            AiState::Fleeing => "fleeing".to_string(),
+        };
+        let _ = writeln!(out, "ai = {}", state);
+    }
+    for effect in entity.effects.iter() {
+        let by = if effect.from_player { " player" } else { "" };
+        let _ = writeln!(out, "effect = {} {} {}{}", effect.kind.name(), effect.turns, effect.potency, by);
+    }
+    Ok(())
+}
+
+/// Rebuilds an entity from a section written by `write_entity`.
+pub fn read_entity(section: &Section) -> Result<Entity, DataError> {
+    let pos_field = section.get("pos").ok_or_else(|| section.error("entity has no pos"))?;
+    let pos = match numbers(pos_field)?.as_slice() {
+        [x, y] => Pos::new(*x, *y),
+        _ => return Err(pos_field.error("pos should be two numbers")),
+    };
+
+    if section.kind == "npc" {
+        return read_npc(section, pos);
+    }
+
+    let level = match section.get("level") {
+        Some(f) => f.value.parse().map_err(|_| f.error("level should be a number"))?,
+        None => 1,
+    };
+    let mut entity = match section.kind.as_str() {
+        "item" if section.name == item::GOLD_ID => Some(item::on_floor(item::gold(0), pos)),
+        "item" => item::make(&section.name).map(|i| item::on_floor(i, pos)),
+        "monster" => monster::create(&section.name, pos, level),
+        "corpse" => monster::create(&section.name, pos, level).map(|mut e| {
+            e.make_corpse();
+            e
+        }),
+        kind => return Err(section.error(format!("unknown entity kind '{}'", kind))),
+    }
+    .ok_or_else(|| section.error(format!("no {} called '{}'", section.kind, section.name)))?;
+
+    for field in &section.fields {
+        match field.key.as_str() {
+            "pos" | "level" => {}
+            "fighter" => {
+                let Some(fighter) = entity.fighter.as_mut() else { continue };
+                let [hp, max_hp, attack, defense, hit_chance] = numbers(field)?[..] else {
+                    return Err(field.error("fighter should be five numbers"));
+                };
+                fighter.hp = hp;
+                fighter.max_hp = max_hp;
+                fighter.attack = attack;
+                fighter.defense = defense;
+                fighter.hit_chance = hit_chance;
+            }
+            "energy" => {
+                let Some(energy) = entity.energy.as_mut() else { continue };
+                let [value] = numbers(field)?[..] else {
+                    return Err(field.error("energy should be one number"));
+                };
+                energy.energy = value;
+            }
+            "ai" => {
+                let Some(ai) = entity.ai.as_mut() else { continue };
+                let words: Vec<&str> = field.value.split_whitespace().collect();
+                ai.state = match words.as_slice() {
+                    ["wandering"] => AiState::Wandering,
+                    ["fleeing"] => AiState::Fleeing,
+                    ["chasing", x, y] => match (x.parse(), y.parse()) {
+                        (Ok(x), Ok(y)) => AiState::Chasing { last_seen: Pos::new(x, y) },
+                        _ => return Err(field.error("chasing needs a position")),
+                    },
+                    _ => return Err(field.error("unknown ai state")),
+                };
+            }
+            "faction" => entity.faction = Some(field.value.clone()),
+            "mana" => {
+                let Some(book) = entity.spellbook.as_mut() else { continue };
+                let [mana] = numbers(field)?[..] else {
+                    return Err(field.error("mana should be one number"));
+                };
+                book.mana = mana;
+            }
+            "amount" => {
+                let [amount] = numbers(field)?[..] else {
+                    return Err(field.error("amount should be one number"));
+                };
+                if let Some(item) = entity.item.as_mut()
+                    && let ItemKind::Gold(_) = item.kind
+                {
+                    *item = item::gold(amount);
+                }
+            }
+            "effect" => {
+                let words: Vec<&str> = field.value.split_whitespace().collect();
+                let (name, turns, potency, from_player) = match words.as_slice() {
+                    [name, turns, potency] => (name, turns, potency, false),
+                    [name, turns, potency, "player"] => (name, turns, potency, true),
+                    _ => return Err(field.error("effect should be a name, turns, potency and maybe 'player'")),
+                };
+                let kind = StatusKind::from_name(name).ok_or_else(|| field.error("unknown status effect"))?;
+                let (Ok(turns), Ok(potency)) = (turns.parse(), potency.parse()) else {
+                    return Err(field.error("effect turns and potency should be numbers"));
+                };
+                let effect = StatusEffect::new(kind, turns, potency);
+                entity.effects.add(if from_player { effect.by_player() } else { effect });
+            }
+            key => return Err(field.error(format!("unknown key '{}'", key))),
+        }
+    }
+    Ok(entity)
+}
+
+fn read_npc(section: &Section, pos: Pos) -> Result<Entity, DataError> {
+    let field = |key: &str| section.get(key).ok_or_else(|| section.error(format!("npc has no {}", key)));
+    let name = &field("name")?.value;
+    let glyph = field("glyph")?.value.chars().next().unwrap_or('?');
+    let color_field = field("color")?;
+    let color = data::parse_color(&color_field.value).ok_or_else(|| color_field.error("bad colour"))?;
+    let npc = Entity::new(pos, glyph, color, name).blocking().with_dialogue(&section.name);
+    let Some(table) = section.get("shop") else { return Ok(npc) };
+
+    let restocked = match section.get("restocked") {
+        Some(f) => f.value.parse().map_err(|_| f.error("restocked should be a turn number"))?,
+        None => 0,
+    };
+    let mut stock = Vec::new();
+    for field in section.fields.iter().filter(|f| f.key == "stock") {
+        stock.push(item::make(&field.value).ok_or_else(|| field.error(format!("no item called '{}'", field.value)))?);
+    }
+    Ok(npc.with_shop(Shop::new(&table.value, stock, restocked)))
+}
+
+fn numbers(field: &Field) -> Result<Vec<i32>, DataError> {
+    field
+        .value
+        .split_whitespace()
+        .map(|w| w.parse().map_err(|_| field.error(format!("'{}' is not a number", w))))
+        .collect()
+}
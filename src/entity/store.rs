@@ -0,0 +1,126 @@
+// store.rs
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use crate::entity::{Entity, EntityId};
+use crate::geometry::{Pos, Rect};
+use crate::map::chunk::chunk_coords;
+
+/// Owns every entity in the world, keyed by id. Iteration is in id order,
+/// i.e. spawn order, which keeps updates deterministic.
+///
+/// Entities are also bucketed by the chunk they stand in, mirroring
+/// `MapManager::chunks`, so position queries only look at nearby entities.
+/// That index is why positions must be changed through `move_to` rather than
+/// by writing `pos` through `get_mut`.
+pub struct EntityStore {
+    entities: BTreeMap<EntityId, Entity>,
+    by_chunk: HashMap<(i32, i32), BTreeSet<EntityId>>,
+    next_id: u32,
+}
+
+impl EntityStore {
+    pub fn new() -> Self {
+        Self {
+            entities: BTreeMap::new(),
+            by_chunk: HashMap::new(),
+            next_id: 1,
+        }
+    }
+
+    /// Adds an entity to the world and returns its new id.
+    pub fn spawn(&mut self, mut entity: Entity) -> EntityId {
+        let id = EntityId(self.next_id);
+        self.next_id += 1;
+        entity.id = id;
+        self.by_chunk.entry(chunk_coords(entity.pos)).or_default().insert(id);
+        self.entities.insert(id, entity);
+        id
+    }
+
+    pub fn despawn(&mut self, id: EntityId) -> Option<Entity> {
+        let entity = self.entities.remove(&id)?;
+        self.unindex(id, entity.pos);
+        Some(entity)
+    }
+
+    /// Moves an entity, keeping the chunk index up to date.
+    pub fn move_to(&mut self, id: EntityId, pos: Pos) {
+        let Some(entity) = self.entities.get_mut(&id) else { return };
+        let old = std::mem::replace(&mut entity.pos, pos);
+        if chunk_coords(old) != chunk_coords(pos) {
+            self.unindex(id, old);
+            self.by_chunk.entry(chunk_coords(pos)).or_default().insert(id);
+        }
+    }
+
+    fn unindex(&mut self, id: EntityId, pos: Pos) {
+        let key = chunk_coords(pos);
+        if let Some(bucket) = self.by_chunk.get_mut(&key) {
+            bucket.remove(&id);
+            if bucket.is_empty() {
+                self.by_chunk.remove(&key);
+            }
+        }
+    }
+
+    pub fn get(&self, id: EntityId) -> Option<&Entity> {
+        self.entities.get(&id)
+    }
+
+    pub fn get_mut(&mut self, id: EntityId) -> Option<&mut Entity> {
+        self.entities.get_mut(&id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Entity> {
+        self.entities.values()
+    }
+
+    /// Ids of every entity that takes turns, in spawn order.
+    pub fn actor_ids(&self) -> Vec<EntityId> {
+        self.iter().filter(|e| e.energy.is_some()).map(|e| e.id).collect()
+    }
+
+    /// Everything standing in one chunk, in spawn order.
+    pub fn in_chunk(&self, chunk_x: i32, chunk_y: i32) -> impl Iterator<Item = &Entity> {
+        self.by_chunk
+            .get(&(chunk_x, chunk_y))
+            .into_iter()
+            .flatten()
+            .filter_map(|id| self.entities.get(id))
+    }
+
+    /// Everything inside `rect`, grouped by chunk.
+    pub fn in_rect(&self, rect: Rect) -> impl Iterator<Item = &Entity> {
+        let (min_x, min_y) = chunk_coords(rect.top_left());
+        let (max_x, max_y) = chunk_coords(Pos::new(rect.x + rect.width - 1, rect.y + rect.height - 1));
+        let chunks = Rect::new(min_x, min_y, max_x - min_x + 1, max_y - min_y + 1);
+        chunks
+            .positions()
+            .flat_map(|c| self.in_chunk(c.x, c.y))
+            .filter(move |e| rect.contains(e.pos))
+    }
+
+    /// Everything whose position lies within `radius` of `center`, using the
+    /// same disc as `geometry::circle`.
+    pub fn in_radius(&self, center: Pos, radius: i32) -> impl Iterator<Item = &Entity> {
+        let r2 = radius * radius;
+        self.in_rect(Rect::around(center, radius))
+            .filter(move |e| e.pos.distance_sq(center) <= r2)
+    }
+
+    /// All entities standing on `pos`, in spawn order.
+    pub fn at(&self, pos: Pos) -> impl Iterator<Item = &Entity> {
+        let (cx, cy) = chunk_coords(pos);
+        self.in_chunk(cx, cy).filter(move |e| e.pos == pos)
+    }
+
+    /// Items lying on `pos`, oldest first.
+    pub fn items_at(&self, pos: Pos) -> Vec<EntityId> {
+        self.at(pos).filter(|e| e.item.is_some()).map(|e| e.id).collect()
+    }
+
+    /// The entity blocking `pos`, if any. At most one blocking entity should
+    /// ever share a tile.
+    pub fn blocker_at(&self, pos: Pos) -> Option<EntityId> {
+        self.at(pos).find(|e| e.blocks_movement).map(|e| e.id)
+    }
+}
@@ -0,0 +1,80 @@
+use macroquad::prelude::RED;
+use crate::entity::{Entity, EntityId, EntityStore, Player};
+use crate::geometry::{Pos, Rect};
+use crate::map::chunk::CHUNK_SIZE;
+use crate::progression::Attribute;
+
+#[test]
+fn test_ids_are_stable() {
+    let mut store = EntityStore::new();
+    let a = store.spawn(Entity::new(Pos::new(0, 0), 'a', RED, "a"));
+    let b = store.spawn(Entity::new(Pos::new(1, 0), 'b', RED, "b"));
+    assert_ne!(a, b);
+
+    store.despawn(a);
+    let c = store.spawn(Entity::new(Pos::new(2, 0), 'c', RED, "c"));
+    // Ids are never recycled, and the survivors keep theirs
+    assert_ne!(c, a);
+    assert_eq!(store.get(b).map(|e| e.glyph), Some('b'));
+    assert!(store.get(a).is_none());
+    assert!(store.get(EntityId(999)).is_none());
+}
+
+#[test]
+fn test_position_queries() {
+    let mut store = EntityStore::new();
+    let pos = Pos::new(3, 4);
+    store.spawn(Entity::new(pos, '!', RED, "potion"));
+    assert_eq!(store.blocker_at(pos), None);
+
+    let orc = store.spawn(Entity::new(pos, 'o', RED, "orc").blocking());
+    assert_eq!(store.at(pos).count(), 2);
+    assert_eq!(store.blocker_at(pos), Some(orc));
+    assert_eq!(store.blocker_at(Pos::new(4, 4)), None);
+}
+
+#[test]
+fn test_chunk_index_follows_moves() {
+    let mut store = EntityStore::new();
+    let rat = store.spawn(Entity::new(Pos::new(1, 1), 'r', RED, "rat"));
+    assert_eq!(store.in_chunk(0, 0).count(), 1);
+
+    // Across a chunk border: the old bucket forgets it, the new one has it
+    let far = Pos::new(CHUNK_SIZE + 2, -3);
+    store.move_to(rat, far);
+    assert_eq!(store.in_chunk(0, 0).count(), 0);
+    assert_eq!(store.in_chunk(1, -1).map(|e| e.id).collect::<Vec<_>>(), vec![rat]);
+    assert_eq!(store.at(far).count(), 1);
+    assert_eq!(store.at(Pos::new(1, 1)).count(), 0);
+
+    store.despawn(rat);
+    assert_eq!(store.in_chunk(1, -1).count(), 0);
+}
+
+#[test]
+fn test_area_queries_span_chunks() {
+    let mut store = EntityStore::new();
+    for x in -3..=3 {
+        store.spawn(Entity::new(Pos::new(x, 0), 'x', RED, "x"));
+    }
+    store.spawn(Entity::new(Pos::new(0, 3), 'y', RED, "y"));
+
+    assert_eq!(store.in_rect(Rect::new(-2, 0, 4, 1)).count(), 4);
+    assert_eq!(store.in_radius(Pos::new(0, 0), 2).count(), 5);
+    assert_eq!(store.in_radius(Pos::new(0, 0), 3).count(), 8);
+}
+
+#[test]
+fn test_levelling_raises_player_stats() {
+    let mut player = Player::new(0, 0);
+    player.fighter.hp = 10;
+    assert_eq!(player.gain_xp(20), 1);
+    // The new level's extra max HP comes with the HP to fill it
+    assert_eq!((player.fighter.hp, player.fighter.max_hp), (14, 34));
+
+    assert!(player.raise(Attribute::Strength));
+    assert!(player.raise(Attribute::Perception));
+    assert!(!player.raise(Attribute::Strength));
+    assert_eq!(player.fighter.attack, 6);
+    assert_eq!(player.experience.unspent, 0);
+}
@@ -0,0 +1,147 @@
+// equipment.rs
+//
+// Worn and wielded items and the stat bonuses they grant.
+use std::ops::Add;
+use crate::item::{Item, ItemKind};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum EquipSlot {
+    Weapon,
+    Armour,
+    Ring,
+    Light,
+}
+
+impl EquipSlot {
+    pub const ALL: [EquipSlot; 4] = [EquipSlot::Weapon, EquipSlot::Armour, EquipSlot::Ring, EquipSlot::Light];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            EquipSlot::Weapon => "weapon",
+            EquipSlot::Armour => "armour",
+            EquipSlot::Ring => "ring",
+            EquipSlot::Light => "light",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Flat bonuses added on top of the wearer's base stats.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StatModifiers {
+    pub attack: i32,
+    pub defense: i32,
+    pub fov_radius: i32,
+    pub light_radius: i32,
+}
+
+impl StatModifiers {
+    pub const NONE: StatModifiers = StatModifiers {
+        attack: 0,
+        defense: 0,
+        fov_radius: 0,
+        light_radius: 0,
+    };
+
+    /// Short summary like "+2 atk, +1 def", skipping zero stats.
+    pub fn describe(&self) -> String {
+        [
+            (self.attack, "atk"),
+            (self.defense, "def"),
+            (self.fov_radius, "fov"),
+            (self.light_radius, "light"),
+        ]
+        .iter()
+        .filter(|(v, _)| *v != 0)
+        .map(|(v, name)| format!("{:+} {}", v, name))
+        .collect::<Vec<_>>()
+        .join(", ")
+    }
+}
+
+impl Add for StatModifiers {
+    type Output = StatModifiers;
+
+    fn add(self, rhs: StatModifiers) -> StatModifiers {
+        StatModifiers {
+            attack: self.attack + rhs.attack,
+            defense: self.defense + rhs.defense,
+            fov_radius: self.fov_radius + rhs.fov_radius,
+            light_radius: self.light_radius + rhs.light_radius,
+        }
+    }
+}
+
+/// One item per slot.
+#[derive(Default)]
+pub struct Equipment {
+    slots: [Option<Item>; 4],
+}
+
+impl Equipment {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, slot: EquipSlot) -> Option<&Item> {
+        self.slots[slot.index()].as_ref()
+    }
+
+    /// Puts an equippable item in its slot. Returns whatever it replaced, or
+    /// hands the item straight back if it can't be equipped.
+    pub fn equip(&mut self, item: Item) -> Result<Option<Item>, Item> {
+        let ItemKind::Equipment(slot, _) = item.kind else {
+            return Err(item);
+        };
+        Ok(self.slots[slot.index()].replace(item))
+    }
+
+    pub fn unequip(&mut self, slot: EquipSlot) -> Option<Item> {
+        self.slots[slot.index()].take()
+    }
+
+    /// Combined bonuses of everything worn.
+    pub fn modifiers(&self) -> StatModifiers {
+        self.slots
+            .iter()
+            .flatten()
+            .filter_map(|item| match item.kind {
+                ItemKind::Equipment(_, mods) => Some(mods),
+                _ => None,
+            })
+            .fold(StatModifiers::default(), |acc, m| acc + m)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::item;
+
+    #[test]
+    fn test_equip_swaps_and_sums() {
+        let mut eq = Equipment::new();
+        assert!(eq.equip(item::make("dagger").unwrap()).unwrap().is_none());
+        assert!(eq.equip(item::make("leather_armour").unwrap()).unwrap().is_none());
+        let mods = eq.modifiers();
+        assert_eq!(mods.attack, 2);
+        assert_eq!(mods.defense, 1);
+
+        // Same slot: the old weapon comes back
+        let old = eq.equip(item::make("sword").unwrap()).unwrap();
+        assert_eq!(old.map(|i| i.name), Some("dagger"));
+        assert_eq!(eq.modifiers().attack, 4);
+
+        assert_eq!(eq.unequip(EquipSlot::Weapon).map(|i| i.name), Some("sword"));
+        assert_eq!(eq.modifiers().attack, 0);
+    }
+
+    #[test]
+    fn test_cannot_equip_potion() {
+        let mut eq = Equipment::new();
+        assert!(eq.equip(item::make("healing_potion").unwrap()).is_err());
+    }
+}
@@ -0,0 +1,182 @@
+// faction.rs
+//
+// Who fights whom, loaded from `data/factions.txt`. Monsters belong to a
+// faction; how two monsters get on is looked up between their factions,
+// and how a monster treats the player follows from the player's standing
+// with its faction, which drops when they attack its members.
+use std::collections::HashMap;
+use crate::data::{self, DataError, Section};
+
+const EMBEDDED: &str = include_str!("../data/factions.txt");
+/// Standing at or below which a faction attacks the player on sight.
+pub const HOSTILE_STANDING: i32 = -10;
+/// Standing at or above which a faction counts the player as a friend.
+pub const ALLIED_STANDING: i32 = 50;
+/// Standing lost for attacking a member of a faction that wasn't hostile.
+pub const ATTACK_PENALTY: i32 = 25;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Relation {
+    Hostile,
+    Neutral,
+    Allied,
+}
+
+impl Relation {
+    /// How a faction treats the player at `standing`.
+    pub fn from_standing(standing: i32) -> Self {
+        match standing {
+            s if s <= HOSTILE_STANDING => Relation::Hostile,
+            s if s >= ALLIED_STANDING => Relation::Allied,
+            _ => Relation::Neutral,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Relation::Hostile => "hostile",
+            Relation::Neutral => "neutral",
+            Relation::Allied => "allied",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Faction {
+    pub id: String,
+    pub name: String,
+    hostile: Vec<String>,
+    allied: Vec<String>,
+    /// The player's standing at the start of a game.
+    pub reputation: i32,
+}
+
+pub struct Factions {
+    list: Vec<Faction>,
+}
+
+impl Factions {
+    /// Loads `data/factions.txt`, falling back to the built-in copy. Also
+    /// returns a warning if the file on disk had to be ignored.
+    pub fn load() -> (Self, Option<String>) {
+        data::load("factions.txt", EMBEDDED, Self::from_sections)
+    }
+
+    pub fn from_sections(sections: &[Section]) -> Result<Self, DataError> {
+        let mut list: Vec<Faction> = Vec::new();
+        for section in sections {
+            if section.kind != "faction" {
+                return Err(section.error(format!("unknown section kind '{}'", section.kind)));
+            }
+            if list.iter().any(|f| f.id == section.name) {
+                return Err(section.error(format!("faction '{}' is defined twice", section.name)));
+            }
+            list.push(parse_faction(section)?);
+        }
+
+        // Relations can only name factions that exist
+        for section in sections {
+            for field in section.fields.iter().filter(|f| f.key == "hostile" || f.key == "allied") {
+                if let Some(unknown) = field.value.split_whitespace().find(|id| !list.iter().any(|f| f.id == *id)) {
+                    return Err(field.error(format!("no faction called '{}'", unknown)));
+                }
+            }
+        }
+        Ok(Self { list })
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Faction> {
+        self.list.iter().find(|f| f.id == id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Faction> {
+        self.list.iter()
+    }
+
+    /// How members of faction `a` and faction `b` get on.
+    pub fn relation(&self, a: &str, b: &str) -> Relation {
+        if a == b {
+            return Relation::Allied;
+        }
+        let lists = |from: &str, to: &str, pick: fn(&Faction) -> &Vec<String>| {
+            self.get(from).is_some_and(|f| pick(f).iter().any(|id| id == to))
+        };
+        let either = |pick: fn(&Faction) -> &Vec<String>| lists(a, b, pick) || lists(b, a, pick);
+        if either(|f| &f.hostile) {
+            Relation::Hostile
+        } else if either(|f| &f.allied) {
+            Relation::Allied
+        } else {
+            Relation::Neutral
+        }
+    }
+
+    /// The player's standing with every faction at the start of a game.
+    pub fn starting_reputation(&self) -> HashMap<String, i32> {
+        self.list.iter().map(|f| (f.id.clone(), f.reputation)).collect()
+    }
+}
+
+fn parse_faction(section: &Section) -> Result<Faction, DataError> {
+    let name = section.get("name").ok_or_else(|| section.error("faction needs a name"))?;
+    let ids = |key: &str| -> Vec<String> {
+        section
+            .fields
+            .iter()
+            .filter(|f| f.key == key)
+            .flat_map(|f| f.value.split_whitespace().map(str::to_string))
+            .collect()
+    };
+    let reputation = match section.get("reputation") {
+        Some(f) => f.value.parse().map_err(|_| f.error("reputation should be a number"))?,
+        None => 0,
+    };
+    if let Some(f) = section.fields.iter().find(|f| !["name", "hostile", "allied", "reputation"].contains(&f.key.as_str())) {
+        return Err(f.error(format!("unknown key '{}'", f.key)));
+    }
+
+    Ok(Faction {
+        id: section.name.clone(),
+        name: name.value.clone(),
+        hostile: ids("hostile"),
+        allied: ids("allied"),
+        reputation,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn factions(text: &str) -> Result<Factions, DataError> {
+        Factions::from_sections(&data::parse(text).unwrap())
+    }
+
+    #[test]
+    fn test_builtin_factions_are_valid() {
+        let all = factions(EMBEDDED).unwrap();
+        for monster in crate::monster::MONSTERS {
+            assert!(all.get(monster.faction).is_some(), "{} is in missing faction {}", monster.id, monster.faction);
+        }
+    }
+
+    #[test]
+    fn test_relations_work_both_ways() {
+        let all = factions("[faction a]\nname = A\nhostile = b\n[faction b]\nname = B\nallied = c\n[faction c]\nname = C\n").unwrap();
+        assert_eq!(all.relation("b", "a"), Relation::Hostile);
+        assert_eq!(all.relation("c", "b"), Relation::Allied);
+        assert_eq!(all.relation("a", "c"), Relation::Neutral);
+        assert_eq!(all.relation("c", "c"), Relation::Allied);
+
+        assert!(factions("[faction a]\nname = A\nhostile = nobody\n").is_err());
+        assert!(factions("[faction a]\nhostile = a\n").is_err());
+    }
+
+    #[test]
+    fn test_standing() {
+        assert_eq!(Relation::from_standing(-50), Relation::Hostile);
+        assert_eq!(Relation::from_standing(0), Relation::Neutral);
+        assert_eq!(Relation::from_standing(-ATTACK_PENALTY), Relation::Hostile);
+        assert_eq!(Relation::from_standing(ALLIED_STANDING), Relation::Allied);
+    }
+}
@@ -0,0 +1,323 @@
+// abilities.rs
+//
+// Casting abilities: the player's ability menu and aiming cursor with its
+// area preview, monsters deciding to cast, and applying the effects.
+use macroquad::prelude::*;
+use macroquad::rand;
+use crate::ability::{Ability, AbilityEffect, Shape};
+use crate::entity::EntityId;
+use crate::game_state::{GameState, UiMode, CHAR_HEIGHT, CHAR_WIDTH, MOVE_KEYS};
+use crate::geometry::Pos;
+use crate::map::tile::Tile;
+use crate::noise::Noise;
+use crate::turn::ActionKind;
+use crate::ui;
+
+/// Percent chance a monster uses an attack ability when it could.
+const MONSTER_CAST_CHANCE: i32 = 50;
+/// Monsters heal themselves below this fraction of their health.
+const MONSTER_HEAL_THRESHOLD: f32 = 0.5;
+
+/// Who is casting.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Caster {
+    Player,
+    Monster(EntityId),
+}
+
+impl GameState {
+    /// Teaches the player every ability their level allows.
+    pub(super) fn learn_abilities(&mut self) {
+        let level = self.player.experience.level;
+        for ability in self.abilities.learned_by(level) {
+            if self.player.spellbook.learn(&ability.id) {
+                self.log.add(format!("You learn {}.", ability.name), SKYBLUE);
+            }
+        }
+    }
+
+    /// The player's known ability at `index` in the menu.
+    fn known_ability(&self, index: usize) -> Option<&Ability> {
+        self.abilities.get(self.player.spellbook.known.get(index)?)
+    }
+
+    /// Letters pick an ability; ones that need aiming open the cursor.
+    pub(super) fn ability_menu_input(&mut self, typed: Option<char>) -> Option<ActionKind> {
+        if is_key_pressed(KeyCode::Escape) {
+            self.mode = UiMode::Normal;
+            return None;
+        }
+        let index = typed.and_then(|c| ui::letter_index(c, self.player.spellbook.known.len()))?;
+        let ability = self.known_ability(index)?;
+        if let Err(reason) = self.player.spellbook.check(ability, self.turn()) {
+            self.log.add(reason, GRAY);
+            return None;
+        }
+
+        if ability.shape == Shape::Caster {
+            let id = ability.id.clone();
+            self.mode = UiMode::Normal;
+            self.cast(Caster::Player, &id, self.player.pos);
+            return Some(ActionKind::Cast);
+        }
+        // Attacks start on the nearest enemy, anything else underfoot
+        let cursor = match self.visible_enemies().first() {
+            Some(&enemy) if ability.is_offensive() => self.entities.get(enemy).map_or(self.player.pos, |e| e.pos),
+            _ => self.player.pos,
+        };
+        self.mode = UiMode::Casting(index, cursor);
+        None
+    }
+
+    /// Movement keys steer the cursor and Tab jumps between enemies; Enter
+    /// or Z casts.
+    pub(super) fn casting_input(&mut self, index: usize, cursor: Pos) -> Option<ActionKind> {
+        if is_key_pressed(KeyCode::Escape) {
+            self.mode = UiMode::Normal;
+            return None;
+        }
+        let mut next = cursor;
+        for (key, alt, dir) in MOVE_KEYS {
+            if is_key_pressed(key) || is_key_pressed(alt) {
+                next += dir;
+            }
+        }
+        if is_key_pressed(KeyCode::Tab) {
+            let enemies: Vec<Pos> = self
+                .visible_enemies()
+                .into_iter()
+                .filter_map(|id| self.entities.get(id).map(|e| e.pos))
+                .collect();
+            let current = enemies.iter().position(|p| *p == cursor);
+            if let Some(pos) = enemies.get(current.map_or(0, |i| i + 1) % enemies.len().max(1)) {
+                next = *pos;
+            }
+        }
+        if next != cursor {
+            self.mode = UiMode::Casting(index, next);
+            return None;
+        }
+
+        if !(is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::Z)) {
+            return None;
+        }
+        let ability = self.known_ability(index)?;
+        if !ability.shape.can_target(&self.map, self.player.pos, cursor) {
+            self.log.add("You can't aim there.", GRAY);
+            return None;
+        }
+        let id = ability.id.clone();
+        self.mode = UiMode::Normal;
+        self.cast(Caster::Player, &id, cursor);
+        Some(ActionKind::Cast)
+    }
+
+    /// Lets a monster use an ability instead of its normal turn, if it has
+    /// one worth using. Returns the action if it cast something.
+    pub(super) fn monster_cast(&mut self, id: EntityId) -> Option<ActionKind> {
+        let entity = self.entities.get(id)?;
+        let book = entity.spellbook.as_ref()?;
+        let fighter = entity.fighter?;
+
+        let foe = self.nearest_foe(id).map(|(_, pos)| pos);
+        let hurt = fighter.health_fraction() < MONSTER_HEAL_THRESHOLD;
+        let turn = self.turn();
+
+        let choice = book.known.iter().filter_map(|k| self.abilities.get(k)).find_map(|ability| {
+            if book.check(ability, turn).is_err() {
+                return None;
+            }
+            let heals = ability.effects.iter().any(|e| matches!(e, AbilityEffect::Heal(_)));
+            if hurt && heals && ability.shape == Shape::Caster {
+                return Some((ability.id.clone(), entity.pos));
+            }
+            let target = foe?;
+            let reaches = ability.shape.area(&self.map, entity.pos, target).contains(&target);
+            (reaches && ability.is_offensive() && rand::gen_range(0, 100) < MONSTER_CAST_CHANCE)
+                .then(|| (ability.id.clone(), target))
+        });
+
+        let (ability, target) = choice?;
+        self.cast(Caster::Monster(id), &ability, target);
+        Some(ActionKind::Cast)
+    }
+
+    /// Pays for and applies an ability cast at `target`. The caller checks
+    /// that it can be afforded and aimed there.
+    fn cast(&mut self, caster: Caster, id: &str, target: Pos) {
+        let Some(ability) = self.abilities.get(id).cloned() else { return };
+        let turn = self.turn();
+        let from = match caster {
+            Caster::Player => {
+                self.player.spellbook.pay(&ability, turn);
+                self.log.add(format!("You use {}.", ability.name), SKYBLUE);
+                self.player.pos
+            }
+            Caster::Monster(monster) => {
+                let Some(entity) = self.entities.get_mut(monster) else { return };
+                if let Some(book) = entity.spellbook.as_mut() {
+                    book.pay(&ability, turn);
+                }
+                let (pos, name) = (entity.pos, entity.name.clone());
+                if self.visible_tiles().contains(&pos) {
+                    self.log.add(format!("The {} uses {}.", name, ability.name), VIOLET);
+                }
+                pos
+            }
+        };
+
+        let area = ability.shape.area(&self.map, from, target);
+        for effect in &ability.effects {
+            match *effect {
+                AbilityEffect::Damage(amount) => {
+                    for pos in &area {
+                        self.ability_damage(caster, *pos, amount, &ability.name);
+                    }
+                }
+                AbilityEffect::Heal(amount) => {
+                    for pos in &area {
+                        self.ability_heal(*pos, amount);
+                    }
+                }
+                AbilityEffect::Apply(status) => {
+                    let status = if caster == Caster::Player { status.by_player() } else { status };
+                    for pos in &area {
+                        if *pos == self.player.pos {
+                            self.inflict_on_player(status);
+                        } else if let Some(entity) = self.entities.blocker_at(*pos).and_then(|id| self.entities.get_mut(id))
+                            && entity.fighter.is_some()
+                        {
+                            entity.effects.add(status);
+                        }
+                    }
+                }
+                AbilityEffect::Teleport => self.teleport(caster, target),
+                AbilityEffect::Terrain(c) => {
+                    let Some(tile) = Tile::from_char(c) else { continue };
+                    let mut dug = false;
+                    for pos in &area {
+                        // Never wall anyone in
+                        if !tile.is_walkable() && (*pos == self.player.pos || self.entities.blocker_at(*pos).is_some()) {
+                            continue;
+                        }
+                        dug |= tile.is_walkable() && !self.map.get_tile(*pos).is_walkable();
+                        self.map.set_tile(*pos, tile);
+                    }
+                    if dug {
+                        self.make_noise(target, Noise::Digging.loudness());
+                    }
+                }
+            }
+        }
+    }
+
+    fn ability_damage(&mut self, caster: Caster, pos: Pos, amount: i32, name: &str) {
+        if pos == self.player.pos {
+            self.player.fighter.hp -= amount;
+            self.log.add(format!("The {} hits you for {}.", name, amount), RED);
+            if self.player.fighter.is_dead() {
+                self.kill_player();
+            }
+            return;
+        }
+        let Some(target) = self.entities.blocker_at(pos) else { return };
+        if caster == Caster::Player && self.entities.get(target).is_some_and(|e| e.is_creature()) {
+            self.provoke(target);
+        }
+        let Some(entity) = self.entities.get_mut(target) else { return };
+        let Some(fighter) = entity.fighter.as_mut() else { return };
+
+        fighter.hp -= amount;
+        let dead = fighter.is_dead();
+        let verb = if dead { "kills" } else { "hits" };
+        self.log.add(format!("The {} {} the {} for {}.", name, verb, entity.name, amount), WHITE);
+        self.make_noise(pos, Noise::Combat.loudness());
+        if dead {
+            match caster {
+                Caster::Player => self.player_kill(target),
+                Caster::Monster(_) => self.kill_monster(target),
+            }
+        }
+    }
+
+    fn ability_heal(&mut self, pos: Pos, amount: i32) {
+        let fighter = if pos == self.player.pos {
+            self.log.add("You feel better.", GREEN);
+            &mut self.player.fighter
+        } else {
+            let Some(entity) = self.entities.blocker_at(pos).and_then(|id| self.entities.get_mut(id)) else { return };
+            let Some(fighter) = entity.fighter.as_mut() else { return };
+            fighter
+        };
+        fighter.hp = (fighter.hp + amount).min(fighter.max_hp);
+    }
+
+    /// Moves the caster to `target` if there's room to stand there.
+    fn teleport(&mut self, caster: Caster, target: Pos) {
+        if !self.can_move_to(target) || target == self.player.pos {
+            if caster == Caster::Player {
+                self.log.add("The spell fizzles: there's no room there.", GRAY);
+            }
+            return;
+        }
+        match caster {
+            Caster::Player => {
+                self.load_chunks_around(target);
+                self.player.pos = target;
+                self.visit_chunk(target);
+            }
+            Caster::Monster(id) => self.entities.move_to(id, target),
+        }
+    }
+
+    pub(super) fn render_ability_menu(&self) {
+        if self.mode != UiMode::Abilities {
+            return;
+        }
+        let book = &self.player.spellbook;
+        let footer = format!("Mana {}/{} - Esc to close", book.mana, book.max_mana);
+        let lines: Vec<String> = book
+            .known
+            .iter()
+            .filter_map(|id| self.abilities.get(id))
+            .map(|ability| match book.check(ability, self.turn()) {
+                Ok(()) => format!("{} ({})", ability.name, ability.describe_cost()),
+                Err(_) => format!("{} ({}) - not ready", ability.name, ability.describe_cost()),
+            })
+            .collect();
+        if lines.is_empty() {
+            ui::draw_panel("Abilities", &["You don't know any abilities yet.".to_string()], &footer);
+        } else {
+            ui::draw_menu("Abilities", &lines, &footer);
+        }
+    }
+
+    /// Shades every tile the ability would hit and boxes the cursor.
+    pub(super) fn render_casting(&self) {
+        let UiMode::Casting(index, cursor) = self.mode else { return };
+        let Some(ability) = self.known_ability(index) else { return };
+
+        let viewport = self.viewport();
+        let to_screen = |pos: Pos| {
+            let Pos { x, y } = pos - viewport.top_left();
+            (x as f32 * CHAR_WIDTH, y as f32 * CHAR_HEIGHT)
+        };
+        let area = ability.shape.area(&self.map, self.player.pos, cursor);
+        for pos in area.iter().filter(|p| viewport.contains(**p)) {
+            let (x, y) = to_screen(*pos);
+            draw_rectangle(x, y, CHAR_WIDTH, CHAR_HEIGHT, Color::new(1.0, 0.5, 0.0, 0.35));
+        }
+        if viewport.contains(cursor) {
+            let (x, y) = to_screen(cursor);
+            let color = if area.is_empty() { RED } else { YELLOW };
+            draw_rectangle_lines(x, y, CHAR_WIDTH, CHAR_HEIGHT, 2.0, color);
+        }
+
+        let hint = format!(
+            "{} ({}) - move to aim, Tab next target, Enter to cast, Esc to cancel",
+            ability.name,
+            ability.describe_cost()
+        );
+        draw_text(&hint, 10.0, 16.0, 18.0, YELLOW);
+    }
+}
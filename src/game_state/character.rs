@@ -0,0 +1,130 @@
+// character.rs
+//
+// Experience rewards, levelling up and the character sheet.
+use macroquad::prelude::*;
+use crate::entity::EntityId;
+use crate::faction::Relation;
+use crate::game_state::{GameState, UiMode};
+use crate::geometry::Pos;
+use crate::map::chunk::{chunk_coords, danger_level};
+use crate::monster;
+use crate::progression::Attribute;
+use crate::quest::Event;
+use crate::ui;
+
+/// XP for setting foot in a new chunk, per point of its danger level.
+const EXPLORE_XP: i32 = 3;
+
+impl GameState {
+    /// Awards XP. A level-up opens the character sheet to spend points
+    /// once the player is back on the map.
+    pub(super) fn gain_xp(&mut self, amount: i32) {
+        if amount <= 0 {
+            return;
+        }
+        if self.player.gain_xp(amount) > 0 {
+            self.log.add(format!("You reach level {}!", self.player.experience.level), GOLD);
+            self.learn_abilities();
+            self.pending_level_up = true;
+        }
+    }
+
+    /// Opens the character sheet for a level-up that happened while
+    /// another screen was open.
+    pub(super) fn show_pending_level_up(&mut self) {
+        if self.pending_level_up && self.mode == UiMode::Normal && !self.game_over {
+            self.pending_level_up = false;
+            self.mode = UiMode::Character;
+        }
+    }
+
+    /// XP for a monster the player has just killed, worked out from the
+    /// level it was created at.
+    pub(super) fn kill_xp(&self, target: EntityId) -> i32 {
+        let Some(entity) = self.entities.get(target) else { return 0 };
+        let Some(template) = entity.template else { return 0 };
+        monster::xp_value(template, entity.level)
+    }
+
+    /// Rewards the first step into each chunk.
+    pub(super) fn visit_chunk(&mut self, pos: Pos) {
+        let (chunk_x, chunk_y) = chunk_coords(pos);
+        if self.player.visited.insert((chunk_x, chunk_y)) {
+            let xp = EXPLORE_XP * danger_level(chunk_x, chunk_y);
+            self.log.add(format!("You explore new ground. (+{} XP)", xp), SKYBLUE);
+            self.gain_xp(xp);
+            self.quest_event(Event::Explored);
+        }
+        self.quest_event(Event::Entered(chunk_x, chunk_y));
+    }
+
+    /// Letters spend attribute points. Looking takes no game time.
+    pub(super) fn character_input(&mut self, typed: Option<char>) {
+        if is_key_pressed(KeyCode::Escape) || is_key_pressed(KeyCode::C) {
+            self.mode = UiMode::Normal;
+            return;
+        }
+        let Some(index) = typed.and_then(|c| ui::letter_index(c, Attribute::ALL.len())) else { return };
+        let attribute = Attribute::ALL[index];
+        if self.player.raise(attribute) {
+            self.log.add(
+                format!("{} rises to {}.", attribute.name(), self.player.attributes.get(attribute)),
+                GREEN,
+            );
+        }
+    }
+
+    pub(super) fn render_character(&self) {
+        if self.mode != UiMode::Character {
+            return;
+        }
+        let player = &self.player;
+        let exp = &player.experience;
+        let stats = player.combat_stats();
+
+        let mut lines = vec![
+            format!("Level {}   XP {}/{}", exp.level, exp.xp, exp.next_level_xp()),
+            String::new(),
+        ];
+        for (i, attribute) in Attribute::ALL.iter().enumerate() {
+            lines.push(format!(
+                "({}) {:<10} {:>3}   {}",
+                ui::letter(i),
+                attribute.name(),
+                player.attributes.get(*attribute),
+                attribute.effect()
+            ));
+        }
+        lines.push(String::new());
+        lines.push(format!("HP         {}/{}", stats.hp.max(0), stats.max_hp));
+        lines.push(format!("Attack     {}", stats.attack));
+        lines.push(format!("Defense    {}", stats.defense));
+        lines.push(format!("Mana       {}/{}", player.spellbook.mana, player.spellbook.max_mana));
+        lines.push(format!("To hit     {}%", stats.hit_chance));
+        lines.push(format!("Sight      {} (light {})", player.sight_radius(), player.light_radius()));
+        lines.push(format!("Speed      {}", player.effects.adjust_speed(player.energy.speed)));
+        lines.push(format!("Stealth    {} (footsteps carry {})", player.stealth(), player.footstep_loudness()));
+        lines.push(format!("Search     {}", player.search()));
+        lines.push(format!("Satiation  {} ({})", player.hunger.satiation, player.hunger.state().name()));
+        lines.push(format!("Gold       {}", player.gold));
+        lines.push(format!("Explored   {} chunks", player.visited.len()));
+        for id in self.companions() {
+            let Some(entity) = self.entities.get(id) else { continue };
+            let (Some(fighter), Some(order)) = (entity.fighter, entity.companion) else { continue };
+            lines.push(format!("Companion  {} ({}/{} HP, {})", entity.name, fighter.hp, fighter.max_hp, order.describe()));
+        }
+        lines.push(String::new());
+        for faction in self.factions.iter() {
+            let standing = player.reputation.get(&faction.id).copied().unwrap_or(0);
+            let relation = Relation::from_standing(standing);
+            lines.push(format!("{:<18} {:>4} ({})", faction.name, standing, relation.name()));
+        }
+
+        let (title, footer) = if exp.unspent > 0 {
+            ("Level up!".to_string(), format!("{} points to spend - Esc to save them for later", exp.unspent))
+        } else {
+            ("Character".to_string(), "Esc to close".to_string())
+        };
+        ui::draw_panel(&title, &lines, &footer);
+    }
+}
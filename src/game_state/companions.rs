@@ -0,0 +1,133 @@
+// companions.rs
+//
+// The player's companions: the pet they start with, hiring more, keeping
+// them close and giving them orders.
+use macroquad::prelude::*;
+use crate::companion::Order;
+use crate::entity::EntityId;
+use crate::game_state::{GameState, UiMode};
+use crate::game_state::factions::Foe;
+use crate::geometry::Pos;
+use crate::monster;
+use crate::ui;
+
+/// Monster the player starts out with as a pet.
+const PET: &str = "dog";
+/// A following companion further than this from the player, or left in an
+/// unloaded chunk, catches up by itself.
+const CATCH_UP_DISTANCE: i32 = 24;
+
+impl GameState {
+    /// Everyone following the player, in the order they joined.
+    pub(super) fn companions(&self) -> Vec<EntityId> {
+        let mut ids: Vec<EntityId> = self.entities.iter().filter(|e| e.companion.is_some()).map(|e| e.id).collect();
+        ids.sort();
+        ids
+    }
+
+    /// A free tile close to the player for a companion to appear on.
+    fn free_spot_near_player(&self) -> Option<Pos> {
+        let player = self.player.pos;
+        self.map.find_nearest(player, 3, |p, _| p != player && self.can_move_to(p))
+    }
+
+    pub(super) fn spawn_pet(&mut self) {
+        let Some(pos) = self.free_spot_near_player() else { return };
+        if let Some(pet) = monster::create(PET, pos, 1) {
+            self.entities.spawn(pet.with_companion());
+        }
+    }
+
+    /// Replaces the NPC `npc` with a companion made from `template`.
+    pub(super) fn recruit(&mut self, npc: EntityId, template: &str) {
+        let Some(old) = self.entities.despawn(npc) else { return };
+        let Some(recruit) = monster::create(template, old.pos, 1) else { return };
+        self.log.add(format!("The {} joins you.", recruit.name), GREEN);
+        self.entities.spawn(recruit.with_companion());
+    }
+
+    /// Keeps a companion's orders current and brings it back to the player
+    /// when it's been left far behind. Returns true if that used its turn.
+    pub(super) fn companion_upkeep(&mut self, id: EntityId) -> bool {
+        let Some(entity) = self.entities.get(id) else { return false };
+        let Some(order) = entity.companion else { return false };
+
+        // Nothing left to attack
+        if let Order::Attack(target) = order
+            && !self.entities.get(target).is_some_and(|e| e.is_creature())
+        {
+            if let Some(entity) = self.entities.get_mut(id) {
+                entity.companion = Some(Order::Follow);
+            }
+            return false;
+        }
+
+        let stranded = self.map.chunk_at(entity.pos).is_none();
+        let far = entity.pos.chebyshev(self.player.pos) > CATCH_UP_DISTANCE;
+        if order == Order::Stay || !(stranded || far) {
+            return false;
+        }
+        let Some(pos) = self.free_spot_near_player() else { return false };
+        let name = entity.name.clone();
+        self.entities.move_to(id, pos);
+        self.log.add(format!("Your {} catches up with you.", name), LIGHTGRAY);
+        true
+    }
+
+    /// Who a companion is going after: the target of its attack order, or
+    /// else the nearest enemy it can see.
+    pub(super) fn companion_foe(&self, id: EntityId, order: Order) -> Option<(Foe, Pos)> {
+        match order {
+            Order::Attack(target) => self.entities.get(target).map(|e| (Foe::Monster(target), e.pos)),
+            _ => self.nearest_foe(id),
+        }
+    }
+
+    pub(super) fn start_orders(&mut self) {
+        if self.companions().is_empty() {
+            self.log.add("You have no companions to give orders to.", GRAY);
+            return;
+        }
+        self.mode = UiMode::Orders;
+    }
+
+    /// The orders menu: follow, stay, or attack one of the enemies in sight.
+    fn order_choices(&self) -> Vec<(Order, String)> {
+        let mut choices = vec![
+            (Order::Follow, "Follow me".to_string()),
+            (Order::Stay, "Stay here".to_string()),
+        ];
+        for enemy in self.visible_enemies() {
+            if let Some(entity) = self.entities.get(enemy) {
+                choices.push((Order::Attack(enemy), format!("Attack the {}", entity.name)));
+            }
+        }
+        choices
+    }
+
+    /// Letters give every companion the same order. Takes no game time.
+    pub(super) fn orders_input(&mut self, typed: Option<char>) {
+        if is_key_pressed(KeyCode::Escape) {
+            self.mode = UiMode::Normal;
+            return;
+        }
+        let choices = self.order_choices();
+        let Some(index) = typed.and_then(|c| ui::letter_index(c, choices.len())) else { return };
+        let (order, label) = &choices[index];
+        for id in self.companions() {
+            if let Some(entity) = self.entities.get_mut(id) {
+                entity.companion = Some(*order);
+            }
+        }
+        self.log.add(format!("\"{}!\"", label), SKYBLUE);
+        self.mode = UiMode::Normal;
+    }
+
+    pub(super) fn render_orders(&self) {
+        if self.mode != UiMode::Orders {
+            return;
+        }
+        let lines: Vec<String> = self.order_choices().into_iter().map(|(_, label)| label).collect();
+        ui::draw_menu("Orders", &lines, "Esc to cancel");
+    }
+}
@@ -0,0 +1,86 @@
+// crafting.rs
+//
+// Gathering materials from resource tiles and the crafting screen.
+use macroquad::prelude::*;
+use crate::game_state::{GameState, UiMode};
+use crate::geometry::Pos;
+use crate::item;
+use crate::noise::Noise;
+use crate::turn::ActionKind;
+use crate::ui;
+
+impl GameState {
+    /// Gathers from the resource tile at `pos`: mines an ore vein the player
+    /// bumped into or picks the herbs they're standing on.
+    pub(super) fn harvest(&mut self, pos: Pos) -> Option<ActionKind> {
+        let (id, left) = self.map.get_tile(pos).harvest()?;
+        let item = item::make(id)?;
+        if self.player.inventory.is_full() {
+            self.log.add(format!("Your pack is too full to gather the {}.", item.name), YELLOW);
+            return None;
+        }
+        let mined = !self.map.get_tile(pos).is_walkable();
+        self.log.add(format!("You gather some {}.", item.name), GREEN);
+        let _ = self.player.inventory.add(item);
+        self.map.set_tile(pos, left);
+        if mined {
+            self.make_noise(pos, Noise::Digging.loudness());
+        }
+        Some(ActionKind::PickUp)
+    }
+
+    pub(super) fn start_crafting(&mut self) {
+        if self.recipes.available(&self.player.inventory).is_empty() {
+            self.log.add("You don't have the materials to make anything.", GRAY);
+            return;
+        }
+        self.mode = UiMode::Crafting;
+    }
+
+    /// Letters craft a recipe, using up its ingredients. Takes a turn.
+    pub(super) fn crafting_input(&mut self, typed: Option<char>) -> Option<ActionKind> {
+        if is_key_pressed(KeyCode::Escape) {
+            self.mode = UiMode::Normal;
+            return None;
+        }
+        let available = self.recipes.available(&self.player.inventory);
+        let index = typed.and_then(|c| ui::letter_index(c, available.len()))?;
+        let recipe = available[index].clone();
+
+        let made = recipe.craft(&mut self.player.inventory)?;
+        let mut dropped = false;
+        for item in made {
+            if let Err(item) = self.player.inventory.add(item) {
+                self.entities.spawn(item::on_floor(item, self.player.pos));
+                dropped = true;
+            }
+        }
+        let name = item::template(recipe.makes).map_or("?", |t| t.name);
+        match recipe.count {
+            1 => self.log.add(format!("You craft a {}.", name), GREEN),
+            n => self.log.add(format!("You craft {} {}s.", n, name), GREEN),
+        }
+        if dropped {
+            self.log.add("Your pack is full; what didn't fit is at your feet.", YELLOW);
+        }
+
+        // Stay at the bench while there's more to make
+        if self.recipes.available(&self.player.inventory).is_empty() {
+            self.mode = UiMode::Normal;
+        }
+        Some(ActionKind::UseItem)
+    }
+
+    pub(super) fn render_crafting(&self) {
+        if self.mode != UiMode::Crafting {
+            return;
+        }
+        let lines: Vec<String> = self
+            .recipes
+            .available(&self.player.inventory)
+            .iter()
+            .map(|recipe| recipe.describe())
+            .collect();
+        ui::draw_menu("Craft", &lines, "Pick something to make - Esc to close");
+    }
+}
@@ -0,0 +1,163 @@
+// dialogue.rs
+//
+// Talking to NPCs: walking the conversation graph and applying its effects.
+use macroquad::prelude::*;
+use crate::dialogue::{Effect, END};
+use crate::entity::EntityId;
+use crate::game_state::{GameState, UiMode};
+use crate::item;
+use crate::ui;
+
+/// Characters per line of dialogue text.
+const WRAP_WIDTH: usize = 60;
+
+/// An open conversation.
+pub struct Conversation {
+    pub npc: EntityId,
+    pub speaker: String,
+    pub node: String,
+}
+
+impl GameState {
+    /// Opens a conversation with an NPC the player bumped into.
+    pub(super) fn start_dialogue(&mut self, npc: EntityId) {
+        let Some((speaker, node)) = self
+            .entities
+            .get(npc)
+            .and_then(|e| Some((e.name.clone(), e.dialogue.clone()?)))
+        else {
+            return;
+        };
+        self.mode = UiMode::Dialogue;
+        self.enter_node(npc, speaker, node);
+    }
+
+    fn enter_node(&mut self, npc: EntityId, speaker: String, node: String) {
+        let Some(effects) = self.dialogues.node(&node).map(|n| n.effects.clone()) else {
+            self.end_dialogue();
+            return;
+        };
+        self.conversation = Some(Conversation { npc, speaker, node });
+        for effect in &effects {
+            self.apply_dialogue_effect(effect);
+        }
+    }
+
+    /// Closes the conversation, unless an effect already switched to
+    /// another screen (the shop).
+    fn end_dialogue(&mut self) {
+        self.conversation = None;
+        if self.mode == UiMode::Dialogue {
+            self.mode = UiMode::Normal;
+        }
+    }
+
+    /// Picks a response by letter. Talking takes no game time.
+    pub(super) fn dialogue_input(&mut self, typed: Option<char>) {
+        let Some(conversation) = &self.conversation else {
+            self.end_dialogue();
+            return;
+        };
+        let (npc, speaker) = (conversation.npc, conversation.speaker.clone());
+        let Some(node) = self.dialogues.node(&conversation.node) else {
+            self.end_dialogue();
+            return;
+        };
+        let choices: Vec<_> = node.available_choices(&self.player).cloned().collect();
+
+        // A node with nothing to say back is a dead end any key closes
+        if is_key_pressed(KeyCode::Escape) || (choices.is_empty() && typed.is_some()) {
+            self.end_dialogue();
+            return;
+        }
+        let Some(index) = typed.and_then(|c| ui::letter_index(c, choices.len())) else { return };
+
+        let choice = &choices[index];
+        for effect in &choice.effects {
+            self.apply_dialogue_effect(effect);
+        }
+        if choice.target == END {
+            self.end_dialogue();
+        } else {
+            self.enter_node(npc, speaker, choice.target.clone());
+        }
+    }
+
+    fn apply_dialogue_effect(&mut self, effect: &Effect) {
+        match effect {
+            Effect::Give(id) => self.give_item(id),
+            Effect::Take(id) => {
+                if let Some(index) = self.player.inventory.items.iter().position(|i| i.id == *id)
+                    && let Some(item) = self.player.inventory.remove(index)
+                {
+                    self.log.add(format!("You hand over the {}.", item.name), GRAY);
+                }
+            }
+            Effect::Heal(amount) => {
+                let fighter = &mut self.player.fighter;
+                fighter.hp = (fighter.hp + amount).min(fighter.max_hp);
+                self.log.add("You feel better.", GREEN);
+            }
+            Effect::Set(flag) => {
+                self.player.flags.insert(flag.clone());
+            }
+            Effect::Clear(flag) => {
+                self.player.flags.remove(flag);
+            }
+            Effect::Apply(status) => self.inflict_on_player(*status),
+            Effect::Trade => {
+                if let Some(npc) = self.conversation.as_ref().map(|c| c.npc) {
+                    self.open_shop(npc);
+                }
+            }
+            Effect::Pay(amount) => {
+                // A node's own effects aren't checked against the purse
+                let paid = (*amount).min(self.player.gold);
+                self.player.gold -= paid;
+                self.log.add(format!("You hand over {} gold.", paid), GOLD);
+            }
+            Effect::Join(template) => {
+                if let Some(npc) = self.conversation.as_ref().map(|c| c.npc) {
+                    self.recruit(npc, template);
+                }
+            }
+            Effect::Quest(id) => {
+                self.start_quest(id);
+            }
+        }
+    }
+
+    /// Hands the player a new item, dropping it at their feet if their pack
+    /// is full.
+    pub(super) fn give_item(&mut self, id: &str) {
+        let Some(item) = item::make(id) else { return };
+        let name = item.name;
+        match self.player.inventory.add(item) {
+            Ok(()) => self.log.add(format!("You receive a {}.", name), GREEN),
+            Err(item) => {
+                self.entities.spawn(item::on_floor(item, self.player.pos));
+                self.log.add(format!("Your pack is full; the {} is at your feet.", name), YELLOW);
+            }
+        }
+    }
+
+    pub(super) fn render_dialogue(&self) {
+        if self.mode != UiMode::Dialogue {
+            return;
+        }
+        let Some(conversation) = &self.conversation else { return };
+        let Some(node) = self.dialogues.node(&conversation.node) else { return };
+
+        let mut lines = Vec::new();
+        for paragraph in &node.text {
+            lines.extend(ui::wrap(paragraph, WRAP_WIDTH));
+            lines.push(String::new());
+        }
+        let choices: Vec<_> = node.available_choices(&self.player).take(ui::MAX_MENU_ENTRIES).collect();
+        for (i, choice) in choices.iter().enumerate() {
+            lines.push(format!("({}) {}", ui::letter(i), choice.label));
+        }
+        let footer = if choices.is_empty() { "Press any key" } else { "Esc to leave" };
+        ui::draw_panel(&conversation.speaker, &lines, footer);
+    }
+}
@@ -0,0 +1,91 @@
+// effects.rs
+//
+// Per-turn hooks for the player and monsters, run around each actor's turn
+// by the scheduler: status effects, mana regeneration and hunger.
+use macroquad::prelude::*;
+use crate::entity::EntityId;
+use crate::game_state::GameState;
+use crate::hunger::HungerState;
+use crate::status::{StatusEffect, StatusKind};
+
+impl GameState {
+    pub(super) fn inflict_on_player(&mut self, effect: StatusEffect) {
+        if !self.player.effects.has(effect.kind) {
+            self.log.add(format!("You are {}!", effect.kind.name()), VIOLET);
+        }
+        self.player.effects.add(effect);
+    }
+
+    /// Poison and regeneration as the player's turn comes round.
+    pub(super) fn player_effects_start(&mut self) {
+        let change = self.player.effects.on_turn_start(&mut self.player.fighter);
+        if change < 0 && self.player.effects.has(StatusKind::Poisoned) {
+            self.log.add(format!("The poison burns for {} damage.", -change), VIOLET);
+        }
+        if self.player.fighter.is_dead() {
+            self.kill_player();
+        }
+    }
+
+    pub(super) fn player_effects_end(&mut self) {
+        self.player.spellbook.on_turn();
+        self.player_hunger();
+        for kind in self.player.effects.on_turn_end() {
+            self.log.add(format!("You are no longer {}.", kind.name()), GRAY);
+        }
+    }
+
+    /// Drains a turn's worth of food, warning the player as they get
+    /// hungrier and hurting them once they're starving.
+    fn player_hunger(&mut self) {
+        let before = self.player.hunger.state();
+        let starved = self.player.hunger.on_turn();
+        let after = self.player.hunger.state();
+        if after < before {
+            match after {
+                HungerState::Hungry => self.log.add("You are getting hungry.", YELLOW),
+                HungerState::Weak => self.log.add("You feel weak with hunger.", ORANGE),
+                HungerState::Starving => self.log.add("You are starving!", RED),
+                HungerState::Fed | HungerState::Full => {}
+            }
+        }
+        if starved > 0 {
+            self.player.fighter.hp -= starved;
+            if self.player.fighter.is_dead() {
+                self.log.add("You starve to death.", RED);
+                self.kill_player();
+            }
+        }
+    }
+
+    /// Runs a monster's start-of-turn effects. Returns false if they killed it.
+    pub(super) fn entity_effects_start(&mut self, id: EntityId) -> bool {
+        let Some(entity) = self.entities.get_mut(id) else { return false };
+        let Some(fighter) = entity.fighter.as_mut() else { return true };
+
+        entity.effects.on_turn_start(fighter);
+        if !fighter.is_dead() {
+            return true;
+        }
+        let (pos, name) = (entity.pos, entity.name.clone());
+        let by_player = entity.effects.harmed_by_player();
+        if self.visible_tiles().contains(&pos) {
+            self.log.add(format!("The {} succumbs to poison.", name), GRAY);
+        }
+        if by_player {
+            self.player_kill(id);
+        } else {
+            self.kill_monster(id);
+        }
+        false
+    }
+
+    pub(super) fn entity_effects_end(&mut self, id: EntityId) {
+        if let Some(entity) = self.entities.get_mut(id) {
+            entity.effects.on_turn_end();
+            if let Some(book) = entity.spellbook.as_mut() {
+                book.on_turn();
+            }
+        }
+    }
+}
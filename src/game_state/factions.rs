@@ -0,0 +1,129 @@
+// factions.rs
+//
+// Who the player and the monsters count as enemies, picking targets, and
+// monsters fighting each other.
+use macroquad::prelude::*;
+use crate::ai::Ai;
+use crate::combat::{self, AttackResult};
+use crate::entity::{Entity, EntityId};
+use crate::faction::{Relation, ATTACK_PENALTY};
+use crate::game_state::GameState;
+use crate::geometry::Pos;
+use crate::noise::Noise;
+
+/// Who a monster is going after.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum Foe {
+    Player,
+    Monster(EntityId),
+}
+
+impl GameState {
+    /// The player's standing with `faction`.
+    fn standing(&self, faction: &str) -> i32 {
+        self.player.reputation.get(faction).copied().unwrap_or(0)
+    }
+
+    /// How the creature `id` and the player get on. Anything that isn't a
+    /// creature is neutral, companions are allies and a creature without a
+    /// faction is an enemy.
+    pub(super) fn relation_to_player(&self, id: EntityId) -> Relation {
+        let Some(entity) = self.entities.get(id).filter(|e| e.is_creature()) else { return Relation::Neutral };
+        if entity.companion.is_some() {
+            return Relation::Allied;
+        }
+        match &entity.faction {
+            Some(faction) => Relation::from_standing(self.standing(faction)),
+            None => Relation::Hostile,
+        }
+    }
+
+    pub(super) fn is_hostile(&self, id: EntityId) -> bool {
+        self.relation_to_player(id) == Relation::Hostile
+    }
+
+    /// How two creatures get on. Companions take the player's side.
+    fn relation_between(&self, a: &Entity, b: &Entity) -> Relation {
+        match (a.companion.is_some(), b.companion.is_some()) {
+            (true, true) => return Relation::Allied,
+            (true, false) => return self.relation_to_player(b.id),
+            (false, true) => return self.relation_to_player(a.id),
+            (false, false) => {}
+        }
+        match (&a.faction, &b.faction) {
+            (Some(a), Some(b)) => self.factions.relation(a, b),
+            _ => Relation::Neutral,
+        }
+    }
+
+    /// The nearest enemy the monster `id` can see, player or monster.
+    pub(super) fn nearest_foe(&self, id: EntityId) -> Option<(Foe, Pos)> {
+        let entity = self.entities.get(id)?;
+        let ai = entity.ai?;
+        let ai = Ai { sight_radius: entity.effects.adjust_sight(ai.sight_radius), ..ai };
+
+        let mut foes: Vec<(Foe, Pos)> = self
+            .entities
+            .in_radius(entity.pos, ai.sight_radius)
+            .filter(|e| e.id != id && e.is_creature() && self.relation_between(entity, e) == Relation::Hostile)
+            .map(|e| (Foe::Monster(e.id), e.pos))
+            .collect();
+        if self.is_hostile(id) {
+            foes.push((Foe::Player, self.player.pos));
+        }
+        foes.into_iter()
+            .filter(|(_, pos)| ai.can_see(&self.map, entity.pos, *pos))
+            .min_by_key(|(_, pos)| pos.distance_sq(entity.pos))
+    }
+
+    /// The player has attacked `id`. Attacking a faction that wasn't
+    /// hostile costs standing with it; companions don't hold a grudge.
+    pub(super) fn provoke(&mut self, id: EntityId) {
+        if self.is_hostile(id) {
+            return;
+        }
+        let Some(faction) = self.entities.get(id).filter(|e| e.companion.is_none()).and_then(|e| e.faction.clone()) else {
+            return;
+        };
+        let standing = self.player.reputation.entry(faction.clone()).or_insert(0);
+        *standing -= ATTACK_PENALTY;
+        let turned = Relation::from_standing(*standing) == Relation::Hostile;
+
+        let name = self.factions.get(&faction).map_or(faction.clone(), |f| f.name.clone());
+        if turned {
+            self.log.add(format!("You have made enemies of {}!", name), ORANGE);
+        } else {
+            self.log.add(format!("{} won't forget that.", capitalise(&name)), ORANGE);
+        }
+    }
+
+    /// One monster attacks another.
+    pub(super) fn monster_fight(&mut self, attacker: EntityId, defender: EntityId) {
+        let Some(entity) = self.entities.get(attacker) else { return };
+        let Some(fighter) = entity.fighter else { return };
+        let name = entity.name.clone();
+        let visible = self.visible_tiles();
+
+        let Some(entity) = self.entities.get_mut(defender) else { return };
+        let Some(target) = entity.fighter.as_mut() else { return };
+        let result = combat::attack(&fighter, target);
+        let pos = entity.pos;
+        if visible.contains(&pos) {
+            let msg = combat::describe(&format!("The {}", name), &format!("the {}", entity.name), result);
+            self.log.add(msg, LIGHTGRAY);
+        }
+        if let (AttackResult::Hit { .. }, Some(effect)) = (result, fighter.on_hit) {
+            entity.effects.add(effect);
+        }
+        self.make_noise(pos, Noise::Combat.loudness());
+
+        if let AttackResult::Kill { .. } = result {
+            self.kill_monster(defender);
+        }
+    }
+}
+
+pub(super) fn capitalise(text: &str) -> String {
+    let mut chars = text.chars();
+    chars.next().map_or(String::new(), |first| first.to_uppercase().chain(chars).collect())
+}
@@ -0,0 +1,43 @@
+// help.rs
+//
+// The key reference screen, opened with '?'.
+use macroquad::prelude::*;
+use crate::game_state::{GameState, UiMode};
+use crate::ui;
+
+/// Key and what it does, in the order they're listed.
+const KEYS: [(&str, &str); 16] = [
+    ("WASD/arrows", "move; bump into monsters to attack, people to talk"),
+    ("Shift+move", "attack the peaceful ones or step onto a known trap"),
+    ("Space", "wait a turn"),
+    ("G", "pick up"),
+    ("I", "use an item"),
+    ("X", "drop an item"),
+    ("E", "equipment"),
+    ("F", "fire the wielded weapon"),
+    ("T", "throw an item"),
+    ("Z", "abilities"),
+    ("K", "craft"),
+    ("H", "search for traps and secret doors"),
+    ("O", "order your companions"),
+    ("Q", "quests"),
+    ("C", "character"),
+    ("?", "this list"),
+];
+
+impl GameState {
+    /// The list is only for reading; Esc or ? closes it.
+    pub(super) fn help_input(&mut self) {
+        if is_key_pressed(KeyCode::Escape) || is_key_pressed(KeyCode::Slash) {
+            self.mode = UiMode::Normal;
+        }
+    }
+
+    pub(super) fn render_help(&self) {
+        if self.mode != UiMode::Help {
+            return;
+        }
+        let lines: Vec<String> = KEYS.iter().map(|(key, what)| format!("{:<12} {}", key, what)).collect();
+        ui::draw_panel("Keys", &lines, "Esc to close");
+    }
+}
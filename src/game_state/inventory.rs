@@ -0,0 +1,254 @@
+// inventory.rs
+//
+// Picking up, using, dropping and equipping items.
+use macroquad::prelude::*;
+use crate::entity::EntityId;
+use crate::equipment::EquipSlot;
+use crate::game_state::{GameState, InventoryAction, UiMode};
+use crate::hunger::HungerState;
+use crate::item::{self, ItemKind, UseEffect};
+use crate::turn::ActionKind;
+use crate::ui;
+
+impl GameState {
+    /// Picks up the item underfoot, or opens a menu if there are several.
+    pub(super) fn start_pickup(&mut self) -> Option<ActionKind> {
+        let items = self.entities.items_at(self.player.pos);
+        match items.as_slice() {
+            [] if self.map.get_tile(self.player.pos).harvest().is_some() => self.harvest(self.player.pos),
+            [] => {
+                self.log.add("There is nothing here to pick up.", GRAY);
+                None
+            }
+            [only] => self.pick_up(*only),
+            _ => {
+                self.mode = UiMode::PickUp;
+                None
+            }
+        }
+    }
+
+    pub(super) fn pickup_input(&mut self, typed: Option<char>) -> Option<ActionKind> {
+        if is_key_pressed(KeyCode::Escape) {
+            self.mode = UiMode::Normal;
+            return None;
+        }
+        let items = self.entities.items_at(self.player.pos);
+        let index = typed.and_then(|c| ui::letter_index(c, items.len()))?;
+        self.mode = UiMode::Normal;
+        self.pick_up(items[index])
+    }
+
+    pub(super) fn inventory_input(&mut self, purpose: InventoryAction, typed: Option<char>) -> Option<ActionKind> {
+        if is_key_pressed(KeyCode::Escape) {
+            self.mode = UiMode::Normal;
+            return None;
+        }
+        let index = typed.and_then(|c| ui::letter_index(c, self.player.inventory.items.len()))?;
+        self.mode = UiMode::Normal;
+        match purpose {
+            InventoryAction::Use => self.use_item(index),
+            InventoryAction::Drop => self.drop_item(index),
+            InventoryAction::Throw => {
+                self.start_throw(index);
+                None
+            }
+        }
+    }
+
+    pub(super) fn equipment_input(&mut self, typed: Option<char>) -> Option<ActionKind> {
+        if is_key_pressed(KeyCode::Escape) {
+            self.mode = UiMode::Normal;
+            return None;
+        }
+        let slots = EquipSlot::ALL.len();
+        let wearable = self.wearable_items();
+        let index = typed.and_then(|c| ui::letter_index(c, slots + wearable.len()))?;
+        // The screen stays open so several changes can be made in a row
+        if index < slots {
+            self.unequip(EquipSlot::ALL[index])
+        } else {
+            self.equip(wearable[index - slots])
+        }
+    }
+
+    /// Inventory indices of the items that can be equipped.
+    fn wearable_items(&self) -> Vec<usize> {
+        self.player
+            .inventory
+            .items
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| matches!(item.kind, ItemKind::Equipment(..)))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    fn equip(&mut self, index: usize) -> Option<ActionKind> {
+        let item = self.player.inventory.remove(index)?;
+        let name = item.name;
+        match self.player.equipment.equip(item) {
+            Ok(previous) => {
+                self.log.add(format!("You equip the {}.", name), WHITE);
+                if let Some(previous) = previous {
+                    // There's always room: the new item's slot just freed up
+                    let _ = self.player.inventory.add(previous);
+                }
+                Some(ActionKind::Equip)
+            }
+            Err(item) => {
+                self.log.add(format!("You can't equip the {}.", item.name), GRAY);
+                let _ = self.player.inventory.add(item);
+                None
+            }
+        }
+    }
+
+    fn unequip(&mut self, slot: EquipSlot) -> Option<ActionKind> {
+        // Nothing to take off
+        self.player.equipment.get(slot)?;
+        if self.player.inventory.is_full() {
+            self.log.add("Your pack is too full to take that off.", GRAY);
+            return None;
+        }
+        let item = self.player.equipment.unequip(slot)?;
+        self.log.add(format!("You take off the {}.", item.name), WHITE);
+        let _ = self.player.inventory.add(item);
+        Some(ActionKind::Equip)
+    }
+
+    fn pick_up(&mut self, id: EntityId) -> Option<ActionKind> {
+        // Coins go in the purse, which never fills up
+        if let Some(ItemKind::Gold(amount)) = self.entities.get(id).and_then(|e| e.item.as_ref()).map(|i| i.kind) {
+            self.entities.despawn(id);
+            self.player.gold += amount;
+            self.log.add(format!("You pick up {} gold.", amount), GOLD);
+            return Some(ActionKind::PickUp);
+        }
+        if self.player.inventory.is_full() {
+            self.log.add("Your pack is full.", GRAY);
+            return None;
+        }
+        let item = self.entities.despawn(id)?.item?;
+        self.log.add(format!("You pick up the {}.", item.name), WHITE);
+        // Can't fail, there was room
+        let _ = self.player.inventory.add(item);
+        Some(ActionKind::PickUp)
+    }
+
+    fn use_item(&mut self, index: usize) -> Option<ActionKind> {
+        let item = self.player.inventory.items.get(index)?;
+        match item.kind {
+            ItemKind::Consumable(UseEffect::Heal(amount)) => {
+                let fighter = &mut self.player.fighter;
+                if fighter.hp >= fighter.max_hp {
+                    self.log.add("You are already at full health.", GRAY);
+                    return None;
+                }
+                fighter.hp = (fighter.hp + amount).min(fighter.max_hp);
+                self.log.add(format!("You use the {} and feel better.", item.name), GREEN);
+            }
+            ItemKind::Consumable(UseEffect::Eat(nutrition)) => {
+                if self.player.hunger.state() == HungerState::Full {
+                    self.log.add("You are too full to eat.", GRAY);
+                    return None;
+                }
+                self.player.hunger.eat(nutrition);
+                self.log.add(format!("You eat the {}.", item.name), GREEN);
+            }
+            ItemKind::Consumable(UseEffect::Apply(effect)) => {
+                self.log.add(format!("You use the {}.", item.name), WHITE);
+                self.inflict_on_player(effect);
+            }
+            ItemKind::Equipment(..) => return self.equip(index),
+            ItemKind::Gold(_) => return None,
+            ItemKind::Missile => {
+                self.log.add(format!("The {} is for throwing (T).", item.name), GRAY);
+                return None;
+            }
+            ItemKind::Material => {
+                self.log.add(format!("The {} is for crafting (K).", item.name), GRAY);
+                return None;
+            }
+            ItemKind::Note(quest) => {
+                let name = item.name;
+                if !self.start_quest(quest) {
+                    self.log.add(format!("You've already read the {}.", name), GRAY);
+                    return None;
+                }
+            }
+        }
+        self.player.inventory.remove(index);
+        Some(ActionKind::UseItem)
+    }
+
+    fn drop_item(&mut self, index: usize) -> Option<ActionKind> {
+        let item = self.player.inventory.remove(index)?;
+        self.log.add(format!("You drop the {}.", item.name), WHITE);
+        self.entities.spawn(item::on_floor(item, self.player.pos));
+        Some(ActionKind::Drop)
+    }
+
+    /// Draws whichever item menu is open.
+    pub(super) fn render_item_menu(&self) {
+        match self.mode {
+            UiMode::PickUp => {
+                let names: Vec<String> = self
+                    .entities
+                    .items_at(self.player.pos)
+                    .into_iter()
+                    .filter_map(|id| self.entities.get(id))
+                    .map(|e| e.name.clone())
+                    .collect();
+                ui::draw_menu("Pick up which item?", &names, "Esc to cancel");
+            }
+            UiMode::Inventory(purpose) => {
+                let title = match purpose {
+                    InventoryAction::Use => "Use which item?",
+                    InventoryAction::Drop => "Drop which item?",
+                    InventoryAction::Throw => "Throw which item?",
+                };
+                let names: Vec<String> = self.player.inventory.items.iter().map(|i| i.name.to_string()).collect();
+                let footer = format!(
+                    "{}/{} carried - Esc to cancel",
+                    self.player.inventory.items.len(),
+                    self.player.inventory.capacity
+                );
+                if names.is_empty() {
+                    ui::draw_panel(title, &["Your pack is empty.".to_string()], &footer);
+                } else {
+                    ui::draw_menu(title, &names, &footer);
+                }
+            }
+            UiMode::Equipment => {
+                let mut lines: Vec<String> = EquipSlot::ALL
+                    .iter()
+                    .map(|slot| match self.player.equipment.get(*slot) {
+                        Some(item) => format!("{:<7} {} ({})", slot.name(), item.name, describe_bonus(&item.kind)),
+                        None => format!("{:<7} -", slot.name()),
+                    })
+                    .collect();
+                lines.extend(
+                    self.wearable_items()
+                        .into_iter()
+                        .map(|i| &self.player.inventory.items[i])
+                        .map(|item| format!("wear {} ({})", item.name, describe_bonus(&item.kind))),
+                );
+                ui::draw_menu("Equipment", &lines, "Pick a slot to take off or an item to wear - Esc to close");
+            }
+            UiMode::Normal | UiMode::Dialogue | UiMode::Shop(..) | UiMode::Character | UiMode::Targeting(..)
+            | UiMode::Abilities
+            | UiMode::Casting(..)
+            | UiMode::Orders
+            | UiMode::Quests
+            | UiMode::Crafting | UiMode::Help => {}
+        }
+    }
+}
+
+fn describe_bonus(kind: &ItemKind) -> String {
+    match kind {
+        ItemKind::Equipment(_, mods) => mods.describe(),
+        _ => String::new(),
+    }
+}
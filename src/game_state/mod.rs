@@ -0,0 +1,670 @@
+// game_state/mod.rs
+mod abilities;
+mod character;
+mod companions;
+mod crafting;
+mod dialogue;
+mod effects;
+mod factions;
+mod help;
+mod inventory;
+mod noise;
+mod quests;
+mod render;
+mod shop;
+mod targeting;
+mod traps;
+
+use std::collections::HashSet;
+use macroquad::prelude::*;
+use crate::ability::Abilities;
+use crate::ai::{self, AiAction};
+use crate::combat::{self, AttackResult};
+use crate::companion;
+use crate::crafting::Recipes;
+use crate::dialogue::Dialogues;
+use crate::entity::{Entity, EntityId, EntityStore, Player};
+use crate::faction::{Factions, Relation};
+use factions::Foe;
+use crate::geometry::{Direction, Pos};
+use crate::item;
+use crate::log::MessageLog;
+use crate::loot::{Loot, LootTables};
+use crate::map::MapManager;
+use crate::map::chunk::{chunk_coords, CHUNK_SIZE};
+use crate::map::tile::Tile;
+use crate::monster;
+use crate::noise::Noise;
+use crate::quest::{Event, Quests};
+use crate::shop::Shop;
+use crate::turn::{ActionKind, TICKS_PER_TURN};
+
+pub const CHAR_WIDTH: f32 = 12.0;
+pub const CHAR_HEIGHT: f32 = 20.0;
+
+// Screen dimensions in characters
+pub const VIEWPORT_WIDTH: i32 = 80;
+pub const VIEWPORT_HEIGHT: i32 = 24;
+
+pub(super) const MOVE_KEYS: [(KeyCode, KeyCode, Direction); 4] = [
+    (KeyCode::W, KeyCode::Up, Direction::North),
+    (KeyCode::S, KeyCode::Down, Direction::South),
+    (KeyCode::A, KeyCode::Left, Direction::West),
+    (KeyCode::D, KeyCode::Right, Direction::East),
+];
+
+/// Message log lines shown under the map.
+pub const LOG_LINES: usize = 3;
+
+/// Chunks further than this from the player's, in chunks, are unloaded.
+const UNLOAD_DISTANCE: i32 = 3;
+/// Floor tiles the player's starting spot must connect to.
+const START_AREA: usize = 30;
+/// Monsters never spawn this close to the player.
+const SPAWN_SAFE_RADIUS: i32 = 8;
+
+/// Which screen has the keyboard.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UiMode {
+    /// Moving around the map.
+    Normal,
+    /// Choosing an inventory item to act on.
+    Inventory(InventoryAction),
+    /// Choosing which of several items on the floor to pick up.
+    PickUp,
+    /// Looking at worn equipment; letters equip or take off items.
+    Equipment,
+    /// Talking to an NPC; letters pick a response.
+    Dialogue,
+    /// Trading with a merchant.
+    Shop(EntityId, TradeAction),
+    /// The character sheet; letters spend attribute points.
+    Character,
+    /// Aiming a shot at an enemy.
+    Targeting(Shot, EntityId),
+    /// Choosing one of the player's abilities.
+    Abilities,
+    /// Aiming the player's ability at this index with a cursor.
+    Casting(usize, Pos),
+    /// Giving orders to companions.
+    Orders,
+    /// The quest log.
+    Quests,
+    /// Choosing a recipe to craft.
+    Crafting,
+    /// The list of keys.
+    Help,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InventoryAction {
+    Use,
+    Drop,
+    Throw,
+}
+
+/// What a ranged attack sends flying.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Shot {
+    /// From the wielded weapon.
+    Fire,
+    /// The inventory item at this index.
+    Throw(usize),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TradeAction {
+    Buy,
+    Sell,
+}
+
+pub struct GameState {
+    pub player: Player,
+    pub entities: EntityStore,
+    pub map: MapManager,
+    pub camera_x: i32,
+    pub camera_y: i32,
+    /// Scheduler ticks elapsed since the start of the game.
+    pub ticks: u64,
+    pub log: MessageLog,
+    /// Set when the player dies; the world stops until a restart.
+    pub game_over: bool,
+    pub mode: UiMode,
+    pub loot: LootTables,
+    pub dialogues: Dialogues,
+    pub abilities: Abilities,
+    pub factions: Factions,
+    pub quests: Quests,
+    pub recipes: Recipes,
+    pub conversation: Option<dialogue::Conversation>,
+    /// Set by a level-up until the character sheet can be shown.
+    pub pending_level_up: bool,
+}
+
+impl GameState {
+    pub fn new(seed: u64) -> Self {
+        let mut map = MapManager::new(seed);
+        let (loot, loot_warning) = LootTables::load();
+        let (dialogues, dialogue_warning) = Dialogues::load();
+        let (abilities, ability_warning) = Abilities::load();
+        let (factions, faction_warning) = Factions::load();
+        let (quests, quest_warning) = Quests::load();
+        let (recipes, recipe_warning) = Recipes::load();
+
+        // Generate initial chunk at 0,0
+        map.generate_chunk_if_needed(0, 0);
+
+        // Spawn on the floor tile nearest the centre of the initial chunk,
+        // skipping pockets too small to get out of
+        let center = Pos::new(CHUNK_SIZE / 2, CHUNK_SIZE / 2);
+        let walkable = |_: Pos, tile: &Tile| tile.is_walkable();
+        let roomy = |pos: Pos, tile: &Tile| {
+            tile.is_walkable() && map.flood_fill(pos, START_AREA, walkable).len() >= START_AREA
+        };
+        let nearest = map
+            .find_nearest(center, CHUNK_SIZE, roomy)
+            .or_else(|| map.find_nearest(center, CHUNK_SIZE, walkable));
+        let start_pos = match nearest {
+            Some(pos) => pos,
+            None => {
+                // The drunkard walk always carves its starting tile, but don't
+                // rely on it: never start the player inside a wall.
+                map.set_tile(center, Tile::floor());
+                center
+            }
+        };
+
+        let mut player = Player::new(start_pos.x, start_pos.y);
+        player.visited.insert(chunk_coords(start_pos));
+        for ability in abilities.learned_by(1) {
+            player.spellbook.learn(&ability.id);
+        }
+        player.reputation = factions.starting_reputation();
+
+        let mut game = Self {
+            player,
+            entities: EntityStore::new(),
+            map,
+            camera_x: 0,
+            camera_y: 0,
+            ticks: 0,
+            log: MessageLog::new(),
+            game_over: false,
+            mode: UiMode::Normal,
+            loot,
+            dialogues,
+            abilities,
+            factions,
+            quests,
+            recipes,
+            conversation: None,
+            pending_level_up: false,
+        };
+        game.log.add(format!("World seed: {}", seed), GRAY);
+        for warning in [loot_warning, dialogue_warning, ability_warning, faction_warning, quest_warning, recipe_warning].into_iter().flatten() {
+            game.log.add(warning, ORANGE);
+        }
+        game.log.add("Welcome! Press ? for the list of keys.", YELLOW);
+        game.populate_chunk(0, 0);
+        game.spawn_pet();
+        game.update_camera();
+        game
+    }
+
+    pub fn update_player(&mut self) {
+        if self.game_over {
+            return;
+        }
+
+        // Only the latest typed character matters; drop the rest so stale
+        // keystrokes don't leak into the next menu that opens.
+        let typed = get_char_pressed();
+        clear_input_queue();
+
+        let action = match self.mode {
+            UiMode::Normal => self.map_input(),
+            UiMode::Inventory(purpose) => self.inventory_input(purpose, typed),
+            UiMode::PickUp => self.pickup_input(typed),
+            UiMode::Equipment => self.equipment_input(typed),
+            UiMode::Dialogue => {
+                self.dialogue_input(typed);
+                None
+            }
+            UiMode::Shop(merchant, action) => {
+                self.shop_input(merchant, action, typed);
+                None
+            }
+            UiMode::Character => {
+                self.character_input(typed);
+                None
+            }
+            UiMode::Targeting(shot, target) => self.targeting_input(shot, target),
+            UiMode::Abilities => self.ability_menu_input(typed),
+            UiMode::Casting(index, cursor) => self.casting_input(index, cursor),
+            UiMode::Orders => {
+                self.orders_input(typed);
+                None
+            }
+            UiMode::Quests => {
+                self.quests_input();
+                None
+            }
+            UiMode::Crafting => self.crafting_input(typed),
+            UiMode::Help => {
+                self.help_input();
+                None
+            }
+        };
+
+        // The world only moves when the player does something
+        if let Some(action) = action {
+            self.end_player_turn(action);
+        }
+
+        self.show_pending_level_up();
+        self.update_camera();
+    }
+
+    /// Input while looking at the map: movement, waiting and menu keys.
+    fn map_input(&mut self) -> Option<ActionKind> {
+        if is_key_pressed(KeyCode::G) {
+            return self.start_pickup();
+        }
+        if is_key_pressed(KeyCode::I) {
+            self.mode = UiMode::Inventory(InventoryAction::Use);
+            return None;
+        }
+        if is_key_pressed(KeyCode::X) {
+            self.mode = UiMode::Inventory(InventoryAction::Drop);
+            return None;
+        }
+        if is_key_pressed(KeyCode::E) {
+            self.mode = UiMode::Equipment;
+            return None;
+        }
+        if is_key_pressed(KeyCode::C) {
+            self.mode = UiMode::Character;
+            return None;
+        }
+        if is_key_pressed(KeyCode::F) {
+            self.start_fire();
+            return None;
+        }
+        if is_key_pressed(KeyCode::T) {
+            self.mode = UiMode::Inventory(InventoryAction::Throw);
+            return None;
+        }
+        if is_key_pressed(KeyCode::Z) {
+            self.mode = UiMode::Abilities;
+            return None;
+        }
+        if is_key_pressed(KeyCode::H) {
+            return Some(self.search());
+        }
+        if is_key_pressed(KeyCode::K) {
+            self.start_crafting();
+            return None;
+        }
+        if is_key_pressed(KeyCode::O) {
+            self.start_orders();
+            return None;
+        }
+        if is_key_pressed(KeyCode::Q) {
+            self.mode = UiMode::Quests;
+            return None;
+        }
+        if is_key_pressed(KeyCode::Slash) {
+            self.mode = UiMode::Help;
+            return None;
+        }
+
+        let mut new_pos = self.player.pos;
+
+        for (key, alt, dir) in MOVE_KEYS {
+            if is_key_pressed(key) || is_key_pressed(alt) {
+                new_pos += dir;
+            }
+        }
+        // Confusion may send the player somewhere else entirely
+        let delta = new_pos - self.player.pos;
+        if let Some(dir) = Direction::from_delta(delta.x, delta.y) {
+            new_pos = self.player.pos + self.player.effects.on_move(dir);
+        }
+        let mut action = is_key_pressed(KeyCode::Space).then_some(ActionKind::Wait);
+
+        self.load_chunks_around(new_pos);
+
+        let blocker = self.entities.blocker_at(new_pos);
+        let creature = blocker.filter(|id| self.entities.get(*id).is_some_and(|e| e.is_creature()));
+        let forced = is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift);
+        if let Some(target) = creature.filter(|id| forced || self.is_hostile(*id)) {
+            // Bump to attack
+            self.player_attack(target);
+            action = Some(ActionKind::Attack);
+        } else if let Some(npc) = blocker.filter(|id| self.entities.get(*id).is_some_and(|e| e.dialogue.is_some())) {
+            // Bump to talk
+            self.start_dialogue(npc);
+        } else if let Some(ally) = creature.filter(|id| self.relation_to_player(*id) == Relation::Allied) {
+            // Friends trade places
+            self.entities.move_to(ally, self.player.pos);
+            self.player.pos = new_pos;
+            self.visit_chunk(new_pos);
+            self.spring_trap(new_pos);
+            action = Some(ActionKind::Move);
+        } else if let Some(other) = creature.and_then(|id| self.entities.get(id)) {
+            self.log.add(format!("The {} is in your way. Hold Shift to attack it.", other.name), GRAY);
+        } else if new_pos != self.player.pos && self.map.get_tile(new_pos).is_closed_door() {
+            self.open_door(new_pos, None);
+            action = Some(ActionKind::Move);
+        } else if new_pos != self.player.pos && !self.map.get_tile(new_pos).is_walkable() {
+            // Bump to mine
+            action = self.harvest(new_pos);
+        } else if let Some(trap) = self
+            .map
+            .hidden_at(new_pos)
+            .and_then(|h| h.known_trap())
+            .filter(|_| new_pos != self.player.pos && !forced)
+        {
+            self.log.add(format!("There is a {} there. Hold Shift to step on it anyway.", trap.name()), GRAY);
+        } else if new_pos != self.player.pos && self.can_move_to(new_pos) {
+            self.player.pos = new_pos;
+            self.visit_chunk(new_pos);
+            self.make_noise(new_pos, self.player.footstep_loudness());
+            self.spring_trap(new_pos);
+            action = Some(ActionKind::Move);
+        }
+
+        action
+    }
+
+    /// Charges the player for `action`, then runs the world until the player
+    /// has enough energy to act again.
+    fn end_player_turn(&mut self, action: ActionKind) {
+        self.player.energy.spend(action);
+        self.check_fetch_quests();
+        if !self.game_over {
+            self.notice_hidden();
+        }
+        self.player_effects_end();
+        while !self.player.energy.can_act() && !self.game_over {
+            self.tick();
+        }
+        if !self.game_over {
+            self.player_effects_start();
+        }
+    }
+
+    /// One scheduler tick: every actor gains energy and those with enough
+    /// take their turns.
+    fn tick(&mut self) {
+        self.ticks += 1;
+        let speed = self.player.effects.adjust_speed(self.player.energy.speed);
+        self.player.energy.gain(speed);
+
+        for id in self.entities.actor_ids() {
+            if let Some(entity) = self.entities.get_mut(id)
+                && let Some(energy) = entity.energy.as_mut()
+            {
+                energy.gain(entity.effects.adjust_speed(energy.speed));
+            }
+            while self.entities.get(id).and_then(|e| e.energy).is_some_and(|e| e.can_act()) {
+                if !self.entity_effects_start(id) {
+                    break;
+                }
+                let action = self.monster_turn(id);
+                if let Some(energy) = self.entities.get_mut(id).and_then(|e| e.energy.as_mut()) {
+                    energy.spend(action);
+                }
+                self.entity_effects_end(id);
+            }
+        }
+    }
+
+    /// Whole game turns elapsed, in units of a normal-speed action.
+    pub fn turn(&self) -> u64 {
+        self.ticks / TICKS_PER_TURN
+    }
+
+    fn update_camera(&mut self) {
+        // Follow the player (center player)
+        self.camera_x = self.player.pos.x - VIEWPORT_WIDTH / 2;
+        self.camera_y = self.player.pos.y - VIEWPORT_HEIGHT / 2;
+    }
+
+    /// Makes sure the chunk at `pos` and its neighbours are loaded, and
+    /// unloads chunks far away, entities and all.
+    fn load_chunks_around(&mut self, pos: Pos) {
+        let (chunk_x, chunk_y) = chunk_coords(pos);
+
+        for y in -1..=1 {
+            for x in -1..=1 {
+                let load = self.map.generate_chunk_if_needed(chunk_x + x, chunk_y + y);
+                for entity in load.restored {
+                    self.entities.spawn(entity);
+                }
+                for warning in load.warnings {
+                    self.log.add(warning, ORANGE);
+                }
+                if load.generated {
+                    self.populate_chunk(chunk_x + x, chunk_y + y);
+                }
+            }
+        }
+
+        let far: Vec<(i32, i32)> = self
+            .map
+            .chunks
+            .keys()
+            .filter(|(x, y)| (x - chunk_x).abs().max((y - chunk_y).abs()) > UNLOAD_DISTANCE)
+            .copied()
+            .collect();
+        for (x, y) in far {
+            // Companions are never left behind with a chunk
+            let ids: Vec<EntityId> = self.entities.in_chunk(x, y).filter(|e| e.companion.is_none()).map(|e| e.id).collect();
+            let entities: Vec<_> = ids.into_iter().filter_map(|id| self.entities.despawn(id)).collect();
+            for warning in self.map.unload_chunk(x, y, &entities) {
+                self.log.add(warning, ORANGE);
+            }
+        }
+    }
+
+    /// Rolls the chunk spawn tables for a freshly generated chunk and
+    /// scatters the results over its floor.
+    fn populate_chunk(&mut self, chunk_x: i32, chunk_y: i32) {
+        let Some(chunk) = self.map.chunks.get(&(chunk_x, chunk_y)) else {
+            return;
+        };
+        let floors: Vec<Pos> = chunk
+            .bounds()
+            .positions()
+            .filter(|p| self.map.get_tile(*p).is_walkable())
+            .collect();
+        if floors.is_empty() {
+            return;
+        }
+
+        let level = chunk.danger_level();
+        let mut spawns = self.loot.roll("chunk_monsters", level, &chunk.rng);
+        spawns.extend(self.loot.roll("chunk_items", level, &chunk.rng));
+        let placed: Vec<(Loot, Pos)> = spawns
+            .into_iter()
+            .map(|loot| (loot, floors[chunk.rng.gen_range(0, floors.len())]))
+            .collect();
+
+        let npcs: Vec<(usize, Pos)> = (0..self.dialogues.npcs.len())
+            .filter(|i| chunk.rng.gen_range(0, 100) < self.dialogues.npcs[*i].chance)
+            .map(|i| (i, floors[chunk.rng.gen_range(0, floors.len())]))
+            .collect();
+
+        for (loot, pos) in placed {
+            if let Loot::Monster(_) = loot
+                && (pos.chebyshev(self.player.pos) < SPAWN_SAFE_RADIUS || !self.can_move_to(pos))
+            {
+                continue;
+            }
+            self.spawn_loot(&loot, pos, level);
+        }
+        for (index, pos) in npcs {
+            if !self.can_move_to(pos) {
+                continue;
+            }
+            let npc = &self.dialogues.npcs[index];
+            let mut entity = Entity::new(pos, npc.glyph, npc.color, &npc.name).blocking().with_dialogue(&npc.talk);
+            if let Some(table) = &npc.shop {
+                entity = entity.with_shop(Shop::new(table, self.roll_stock(table, pos), self.turn()));
+            }
+            self.entities.spawn(entity);
+        }
+    }
+
+    fn spawn_loot(&mut self, loot: &Loot, pos: Pos, level: i32) {
+        let entity = match loot {
+            Loot::Monster(id) => monster::create(id, pos, level),
+            Loot::Item(id) => item::make(id).map(|item| item::on_floor(item, pos)),
+            Loot::Gold(amount) => Some(item::on_floor(item::gold(*amount), pos)),
+        };
+        if let Some(entity) = entity {
+            self.entities.spawn(entity);
+        }
+    }
+
+    /// Leaves a corpse where a monster died and rolls its drop table, using
+    /// the RNG of the chunk it died in.
+    fn kill_monster(&mut self, id: EntityId) {
+        let Some(entity) = self.entities.get_mut(id) else { return };
+        if entity.companion.is_some() {
+            self.log.add(format!("Your {} dies!", entity.name), RED);
+        }
+        entity.make_corpse();
+        let pos = entity.pos;
+        let Some(template) = entity.template else { return };
+
+        let Some(chunk) = self.map.chunk_at(pos) else { return };
+        let level = chunk.danger_level();
+        let drops = self.loot.roll(&format!("drops_{}", template), level, &chunk.rng);
+        for loot in drops {
+            self.spawn_loot(&loot, pos, level);
+        }
+    }
+
+    /// Lets an AI-driven entity act once. Returns what the action cost.
+    fn monster_turn(&mut self, id: EntityId) -> ActionKind {
+        if let Some(action) = self.monster_cast(id) {
+            return action;
+        }
+        if self.companion_upkeep(id) {
+            return ActionKind::Move;
+        }
+        let Some(entity) = self.entities.get(id) else { return ActionKind::Wait };
+        let Some(mut ai) = entity.ai else { return ActionKind::Wait };
+        ai.sight_radius = entity.effects.adjust_sight(ai.sight_radius);
+
+        let player_pos = self.player.pos;
+        let foe = match entity.companion {
+            Some(order) => self.companion_foe(id, order),
+            None => self.nearest_foe(id),
+        };
+        let enemy = foe.map(|(_, pos)| pos);
+        // Closed doors count as open ground: walking into one opens it
+        let walkable = |p: Pos| p != player_pos && (self.can_move_to(p) || self.map.get_tile(p).is_closed_door());
+        let (state, action) = match entity.companion {
+            Some(order) => companion::think(entity, &ai, order, player_pos, enemy, &self.map, walkable),
+            None => ai::think(entity, &ai, enemy, &self.map, walkable),
+        };
+
+        let Some(entity) = self.entities.get_mut(id) else { return ActionKind::Wait };
+        if let Some(ai) = entity.ai.as_mut() {
+            ai.state = state;
+        }
+        match action {
+            AiAction::Move(dir) => {
+                // A confused monster may stumble into a wall and lose its turn
+                let target = entity.pos + entity.effects.on_move(dir);
+                if self.map.get_tile(target).is_closed_door() {
+                    self.open_door(target, Some(id));
+                } else if target != player_pos && self.can_move_to(target) {
+                    self.entities.move_to(id, target);
+                }
+                ActionKind::Move
+            }
+            AiAction::Wait => ActionKind::Wait,
+            AiAction::Attack => {
+                match foe {
+                    Some((Foe::Player, _)) => self.monster_attack(id),
+                    Some((Foe::Monster(target), _)) => self.monster_fight(id, target),
+                    None => {}
+                }
+                ActionKind::Attack
+            }
+        }
+    }
+
+    fn player_attack(&mut self, target: EntityId) {
+        self.provoke(target);
+        let Some(entity) = self.entities.get_mut(target) else { return };
+        let Some(fighter) = entity.fighter.as_mut() else { return };
+
+        let result = combat::attack(&self.player.combat_stats(), fighter);
+        let pos = entity.pos;
+        let msg = combat::describe("You", &format!("the {}", entity.name), result);
+        self.log.add(msg, WHITE);
+        self.make_noise(pos, Noise::Combat.loudness());
+
+        if let AttackResult::Kill { .. } = result {
+            self.player_kill(target);
+        }
+    }
+
+    /// Kills a monster the player brought down and awards XP for it.
+    fn player_kill(&mut self, target: EntityId) {
+        let xp = self.kill_xp(target);
+        let template = self.entities.get(target).and_then(|e| e.template);
+        self.kill_monster(target);
+        self.gain_xp(xp);
+        if let Some(template) = template {
+            self.quest_event(Event::Killed(template));
+        }
+    }
+
+    fn monster_attack(&mut self, attacker: EntityId) {
+        let Some(entity) = self.entities.get(attacker) else { return };
+        let Some(fighter) = entity.fighter else { return };
+
+        let mut defender = self.player.combat_stats();
+        let result = combat::attack(&fighter, &mut defender);
+        self.player.fighter.hp = defender.hp;
+        let msg = combat::describe(&format!("The {}", entity.name), "you", result);
+        self.log.add(msg, if result == AttackResult::Miss { GRAY } else { RED });
+        self.make_noise(self.player.pos, Noise::Combat.loudness());
+
+        match result {
+            AttackResult::Hit { .. } => {
+                if let Some(effect) = fighter.on_hit {
+                    self.inflict_on_player(effect);
+                }
+            }
+            AttackResult::Kill { .. } => self.kill_player(),
+            AttackResult::Miss => {}
+        }
+    }
+
+    fn kill_player(&mut self) {
+        self.game_over = true;
+        self.mode = UiMode::Normal;
+        self.log.add("You die...", RED);
+    }
+
+    /// Everything the player can currently see.
+    pub fn visible_tiles(&self) -> HashSet<Pos> {
+        self.map.field_of_view(self.player.pos, self.player.sight_radius())
+    }
+
+    pub fn can_move_to(&self, pos: Pos) -> bool {
+        // Ensure chunk exists (it should, because we generate around player, but good to be safe)
+        // Actually update_player generates them.
+
+        // A hostile entity in the way also blocks; the caller decides
+        // whether to attack it instead.
+        self.map.get_tile(pos).is_walkable() && self.entities.blocker_at(pos).is_none()
+    }
+}
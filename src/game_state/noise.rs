@@ -0,0 +1,55 @@
+// noise.rs
+//
+// Making noise and the monsters that come to investigate it, and doors,
+// which make some of it.
+use macroquad::prelude::*;
+use crate::ai::AiState;
+use crate::entity::EntityId;
+use crate::faction::Relation;
+use crate::game_state::GameState;
+use crate::geometry::Pos;
+use crate::map::tile::Tile;
+use crate::noise::{self, Noise};
+
+impl GameState {
+    /// Makes a noise at `origin` carrying `loudness` steps. Every monster
+    /// it reaches that isn't running away or on the player's side heads over
+    /// to investigate.
+    pub(super) fn make_noise(&mut self, origin: Pos, loudness: i32) {
+        let listeners: Vec<(EntityId, Pos, i32)> = self
+            .entities
+            .in_radius(origin, noise::reach(loudness, noise::MAX_HEARING))
+            .filter(|e| e.companion.is_none() && self.relation_to_player(e.id) != Relation::Allied)
+            .filter_map(|e| Some((e.id, e.pos, noise::reach(loudness, e.ai?.hearing_radius))))
+            .filter(|(_, pos, reach)| pos.manhattan(origin) <= *reach)
+            .collect();
+        let Some(furthest) = listeners.iter().map(|(_, _, reach)| *reach).max() else { return };
+
+        let spread = self.map.noise_spread(origin, furthest);
+        for (id, pos, reach) in listeners {
+            if spread.get(&pos).is_none_or(|steps| *steps > reach) {
+                continue;
+            }
+            if let Some(ai) = self.entities.get_mut(id).and_then(|e| e.ai.as_mut())
+                && ai.state != AiState::Fleeing
+            {
+                ai.state = AiState::Chasing { last_seen: origin };
+            }
+        }
+    }
+
+    /// Opens the closed door at `pos`; `opener` is the monster doing it, or
+    /// `None` for the player.
+    pub(super) fn open_door(&mut self, pos: Pos, opener: Option<EntityId>) {
+        self.map.set_tile(pos, Tile::open_door());
+        match opener.and_then(|id| self.entities.get(id)) {
+            None => self.log.add("You open the door.", LIGHTGRAY),
+            Some(entity) => {
+                if self.visible_tiles().contains(&entity.pos) {
+                    self.log.add(format!("The {} opens a door.", entity.name), LIGHTGRAY);
+                }
+            }
+        }
+        self.make_noise(pos, Noise::Door.loudness());
+    }
+}
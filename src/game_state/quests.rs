@@ -0,0 +1,120 @@
+// quests.rs
+//
+// Taking on quests, feeding them game events, paying out rewards and the
+// quest log screen.
+use macroquad::prelude::*;
+use crate::game_state::factions::capitalise;
+use crate::game_state::{GameState, UiMode};
+use crate::map::chunk::chunk_coords;
+use crate::quest::{Event, Goal, Reward};
+use crate::ui;
+
+/// Characters per line of quest text.
+const WRAP_WIDTH: usize = 60;
+
+impl GameState {
+    /// Adds a quest to the log. Returns false if the player already has it.
+    pub(super) fn start_quest(&mut self, id: &str) -> bool {
+        let Some(quest) = self.quests.get(id) else { return false };
+        if !self.player.quests.start(id) {
+            return false;
+        }
+        self.log.add(format!("New quest: {}. {}", quest.name, quest.text), GOLD);
+        // The player may already be carrying what a fetch quest wants, or
+        // standing where a reach quest points
+        self.check_fetch_quests();
+        let (chunk_x, chunk_y) = chunk_coords(self.player.pos);
+        self.quest_event(Event::Entered(chunk_x, chunk_y));
+        true
+    }
+
+    /// Counts an event towards the player's quests, finishing any it
+    /// completes.
+    pub(super) fn quest_event(&mut self, event: Event) {
+        for id in self.player.quests.on_event(&self.quests, event) {
+            self.complete_quest(&id);
+        }
+    }
+
+    /// Tells fetch quests how many of their item the player is carrying.
+    pub(super) fn check_fetch_quests(&mut self) {
+        let wanted: Vec<&'static str> = self
+            .player
+            .quests
+            .active
+            .iter()
+            .filter_map(|p| match self.quests.get(&p.quest)?.goal {
+                Goal::Fetch { item, .. } => Some(item),
+                _ => None,
+            })
+            .collect();
+        for item in wanted {
+            let carried = self.player.inventory.items.iter().filter(|i| i.id == item).count() as i32;
+            self.quest_event(Event::Carrying(item, carried));
+        }
+    }
+
+    /// Takes what a fetch quest asked for and pays out the rewards.
+    fn complete_quest(&mut self, id: &str) {
+        let Some(quest) = self.quests.get(id).cloned() else { return };
+        self.log.add(format!("Quest complete: {}!", quest.name), GOLD);
+
+        if let Goal::Fetch { item, count } = quest.goal {
+            for _ in 0..count {
+                let Some(index) = self.player.inventory.items.iter().position(|i| i.id == item) else { break };
+                self.player.inventory.remove(index);
+            }
+        }
+        for reward in &quest.rewards {
+            match reward {
+                Reward::Gold(amount) => {
+                    self.player.gold += amount;
+                    self.log.add(format!("You receive {} gold.", amount), GOLD);
+                }
+                Reward::Xp(amount) => {
+                    self.log.add(format!("You gain {} XP.", amount), SKYBLUE);
+                    self.gain_xp(*amount);
+                }
+                Reward::Item(item) => self.give_item(item),
+                Reward::Reputation(faction, amount) => {
+                    *self.player.reputation.entry(faction.clone()).or_insert(0) += amount;
+                    if let Some(faction) = self.factions.get(faction) {
+                        self.log.add(format!("{} think better of you.", capitalise(&faction.name)), SKYBLUE);
+                    }
+                }
+            }
+        }
+    }
+
+    /// The log is only for reading; Esc or Q closes it.
+    pub(super) fn quests_input(&mut self) {
+        if is_key_pressed(KeyCode::Escape) || is_key_pressed(KeyCode::Q) {
+            self.mode = UiMode::Normal;
+        }
+    }
+
+    pub(super) fn render_quests(&self) {
+        if self.mode != UiMode::Quests {
+            return;
+        }
+        let log = &self.player.quests;
+        let mut lines = Vec::new();
+        for progress in &log.active {
+            let Some(quest) = self.quests.get(&progress.quest) else { continue };
+            lines.push(quest.name.clone());
+            lines.push(format!("  {}", quest.goal.describe(progress.count)));
+            lines.extend(ui::wrap(&quest.text, WRAP_WIDTH).into_iter().map(|line| format!("  {}", line)));
+            lines.push(String::new());
+        }
+        if log.active.is_empty() {
+            lines.push("You have no quests. Talk to people and read what you find.".to_string());
+            lines.push(String::new());
+        }
+        for id in &log.finished {
+            if let Some(quest) = self.quests.get(id) {
+                lines.push(format!("{} (done)", quest.name));
+            }
+        }
+        ui::draw_panel("Quests", &lines, "Esc to close");
+    }
+}
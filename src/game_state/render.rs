@@ -0,0 +1,212 @@
+// render.rs
+use macroquad::prelude::*;
+use crate::game_state::{GameState, CHAR_HEIGHT, CHAR_WIDTH, LOG_LINES, VIEWPORT_HEIGHT, VIEWPORT_WIDTH};
+use crate::geometry::{Pos, Rect};
+use crate::hunger::{HungerState, MAX_SATIATION};
+use crate::map::chunk::{danger_level, CHUNK_SIZE};
+
+/// Tiles outside the player's sight are drawn at this brightness.
+const REMEMBERED_BRIGHTNESS: f32 = 0.35;
+/// Height of one HUD line, counted up from the bottom of the window.
+const HUD_LINE_HEIGHT: f32 = 20.0;
+
+impl GameState {
+    pub fn render(&self) {
+        clear_background(BLACK);
+
+        // Draw tiles, dimming those the player can't currently see
+        let viewport = self.viewport();
+        let in_sight = self.visible_tiles();
+        for (pos, tile) in self.map.tiles_in_rect(viewport) {
+            let Pos { x, y } = pos - viewport.top_left();
+
+            // Skip drawing empty/void tiles if you want, or draw them as walls
+            // map.get_tile returns wall if not found, so we are good.
+
+            let screen_x = x as f32 * CHAR_WIDTH;
+            let screen_y = y as f32 * CHAR_HEIGHT;
+
+            // Draw background if present
+            if let Some(bg) = tile.bg_color {
+                draw_rectangle(
+                    screen_x,
+                    screen_y,
+                    CHAR_WIDTH,
+                    CHAR_HEIGHT,
+                    bg,
+                );
+            }
+
+            // Draw character, or the trap on it once it's been found
+            let (glyph, color) = match self.map.hidden_at(pos).and_then(|h| h.known_trap()) {
+                Some(trap) => ('^', trap.color()),
+                None => (tile.char, tile.color),
+            };
+            // Don't draw spaces
+            if glyph != ' ' {
+                let color = if in_sight.contains(&pos) { color } else { dim(color) };
+                draw_text(
+                    &glyph.to_string(),
+                    screen_x,
+                    screen_y + CHAR_HEIGHT - 4.0, // align baseline
+                    CHAR_HEIGHT,
+                    color,
+                );
+            }
+        }
+
+        // Draw entities in sight bottom to top: corpses, then items, then
+        // actors, so whatever matters most on a tile is the glyph you see
+        let mut visible: Vec<_> = self
+            .entities
+            .in_radius(self.player.pos, self.player.sight_radius())
+            .filter(|e| in_sight.contains(&e.pos))
+            .collect();
+        visible.sort_by_key(|e| (e.blocks_movement, e.item.is_some()));
+        for entity in visible {
+            self.draw_glyph(viewport, entity.pos, entity.glyph, entity.color);
+        }
+
+        // Draw player
+        self.draw_glyph(viewport, self.player.pos, '@', self.player.color);
+        self.render_targeting();
+        self.render_casting();
+
+        // Draw UI
+        self.render_hud();
+
+        // Most recent messages, under the map
+        let log_top = VIEWPORT_HEIGHT as f32 * CHAR_HEIGHT + 16.0;
+        for (i, (text, color)) in self.log.recent(LOG_LINES).enumerate() {
+            draw_text(text, 10.0, log_top + i as f32 * 16.0, 16.0, *color);
+        }
+
+        self.render_item_menu();
+        self.render_dialogue();
+        self.render_shop();
+        self.render_character();
+        self.render_ability_menu();
+        self.render_orders();
+        self.render_quests();
+        self.render_crafting();
+        self.render_help();
+
+        if self.game_over {
+            self.render_game_over();
+        }
+    }
+
+    /// Status lines along the bottom of the window, stacked upwards.
+    fn render_hud(&self) {
+        let chunk_x = self.player.pos.x.div_euclid(CHUNK_SIZE);
+        let chunk_y = self.player.pos.y.div_euclid(CHUNK_SIZE);
+        let mut lines = vec![(
+            format!(
+                "Pos: ({}, {}) | Chunk: ({}, {}) | Danger: {} | Turn: {} | FPS: {:.0}",
+                self.player.pos.x,
+                self.player.pos.y,
+                chunk_x,
+                chunk_y,
+                danger_level(chunk_x, chunk_y),
+                self.turn(),
+                get_fps()
+            ),
+            WHITE,
+        )];
+
+        let stats = self.player.combat_stats();
+        lines.push((
+            format!(
+                "Lvl: {} | XP: {}/{} | HP: {}/{} | MP: {}/{} | Atk: {} | Def: {} | FOV: {} | Light: {} | Gold: {}",
+                self.player.experience.level,
+                self.player.experience.xp,
+                self.player.experience.next_level_xp(),
+                stats.hp.max(0),
+                stats.max_hp,
+                self.player.spellbook.mana,
+                self.player.spellbook.max_mana,
+                stats.attack,
+                stats.defense,
+                self.player.fov_radius(),
+                self.player.light_radius(),
+                self.player.gold,
+            ),
+            WHITE,
+        ));
+
+        let hunger = self.player.hunger.state();
+        let hunger_color = match hunger {
+            HungerState::Full | HungerState::Fed => LIGHTGRAY,
+            HungerState::Hungry => YELLOW,
+            HungerState::Weak => ORANGE,
+            HungerState::Starving => RED,
+        };
+        lines.push((
+            format!("Satiation: {}/{} ({})", self.player.hunger.satiation, MAX_SATIATION, hunger.name()),
+            hunger_color,
+        ));
+
+        if self.player.effects.iter().next().is_some() {
+            lines.push((format!("Effects: {}", self.player.effects.describe()), VIOLET));
+        }
+
+        // Whatever the player is standing on
+        let here: Vec<&str> = self.entities.at(self.player.pos).map(|e| e.name.as_str()).collect();
+        if !here.is_empty() {
+            lines.push((format!("Here: {}", here.join(", ")), LIGHTGRAY));
+        }
+
+        for (i, (text, color)) in lines.iter().enumerate() {
+            let y = screen_height() - 10.0 - i as f32 * HUD_LINE_HEIGHT;
+            draw_text(text, 10.0, y, 16.0, *color);
+        }
+    }
+
+    fn render_game_over(&self) {
+        let w = VIEWPORT_WIDTH as f32 * CHAR_WIDTH;
+        let h = VIEWPORT_HEIGHT as f32 * CHAR_HEIGHT;
+        draw_rectangle(0.0, 0.0, w, h, Color::new(0.0, 0.0, 0.0, 0.75));
+
+        let title = "YOU DIED";
+        let size = measure_text(title, None, 48, 1.0);
+        draw_text(title, (w - size.width) / 2.0, h / 2.0 - 20.0, 48.0, RED);
+
+        let hint = format!("You survived {} turns. Press R to start again.", self.turn());
+        let size = measure_text(&hint, None, 20, 1.0);
+        draw_text(&hint, (w - size.width) / 2.0, h / 2.0 + 20.0, 20.0, WHITE);
+    }
+
+    /// The world rectangle on screen.
+    pub(super) fn viewport(&self) -> Rect {
+        Rect::new(self.camera_x, self.camera_y, VIEWPORT_WIDTH, VIEWPORT_HEIGHT)
+    }
+
+    /// Draws a single glyph at a world position, if it's inside the viewport.
+    pub(super) fn draw_glyph(&self, viewport: Rect, pos: Pos, glyph: char, color: Color) {
+        if !viewport.contains(pos) {
+            return;
+        }
+        let Pos { x, y } = pos - viewport.top_left();
+        let screen_x = x as f32 * CHAR_WIDTH;
+        let screen_y = y as f32 * CHAR_HEIGHT;
+
+        // Cover the tile underneath so glyphs don't overlap
+        draw_rectangle(screen_x, screen_y, CHAR_WIDTH, CHAR_HEIGHT, BLACK);
+        draw_text(
+            &glyph.to_string(),
+            screen_x,
+            screen_y + CHAR_HEIGHT - 4.0, // align baseline
+            CHAR_HEIGHT,
+            color,
+        );
+    }
+}
+
+fn dim(color: Color) -> Color {
+    Color::new(
+        color.r * REMEMBERED_BRIGHTNESS,
+        color.g * REMEMBERED_BRIGHTNESS,
+        color.b * REMEMBERED_BRIGHTNESS,
+        color.a,
+    )
+}
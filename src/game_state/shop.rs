@@ -0,0 +1,123 @@
+// shop.rs
+//
+// Buying from and selling to merchants.
+use macroquad::prelude::*;
+use crate::entity::EntityId;
+use crate::game_state::{GameState, TradeAction, UiMode};
+use crate::geometry::Pos;
+use crate::item::{self, Item};
+use crate::loot::Loot;
+use crate::shop::{self, Shop};
+use crate::ui;
+
+impl GameState {
+    /// Rolls a merchant's stock from `table` with the RNG of the chunk at `pos`.
+    pub(super) fn roll_stock(&self, table: &str, pos: Pos) -> Vec<Item> {
+        let Some(chunk) = self.map.chunk_at(pos) else { return Vec::new() };
+        self.loot
+            .roll(table, chunk.danger_level(), &chunk.rng)
+            .into_iter()
+            .filter_map(|loot| match loot {
+                Loot::Item(id) => item::make(id),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub(super) fn open_shop(&mut self, merchant: EntityId) {
+        let turn = self.turn();
+        let Some(entity) = self.entities.get(merchant) else { return };
+        let Some(shop) = &entity.shop else { return };
+
+        if shop.needs_restock(turn) {
+            let stock = self.roll_stock(&shop.table, entity.pos);
+            if let Some(shop) = self.entities.get_mut(merchant).and_then(|e| e.shop.as_mut()) {
+                shop.restock(stock, turn);
+            }
+        }
+        self.mode = UiMode::Shop(merchant, TradeAction::Buy);
+    }
+
+    /// Letters buy or sell; Tab switches between the two lists. Trading
+    /// takes no game time.
+    pub(super) fn shop_input(&mut self, merchant: EntityId, action: TradeAction, typed: Option<char>) {
+        if is_key_pressed(KeyCode::Escape) {
+            self.mode = UiMode::Normal;
+            return;
+        }
+        if is_key_pressed(KeyCode::Tab) {
+            let other = match action {
+                TradeAction::Buy => TradeAction::Sell,
+                TradeAction::Sell => TradeAction::Buy,
+            };
+            self.mode = UiMode::Shop(merchant, other);
+            return;
+        }
+        let Some(stock) = self.entities.get(merchant).and_then(|e| e.shop.as_ref()).map(|s| s.stock.len()) else {
+            self.mode = UiMode::Normal;
+            return;
+        };
+        match action {
+            TradeAction::Buy => {
+                if let Some(index) = typed.and_then(|c| ui::letter_index(c, stock)) {
+                    self.buy(merchant, index);
+                }
+            }
+            TradeAction::Sell => {
+                if let Some(index) = typed.and_then(|c| ui::letter_index(c, self.player.inventory.items.len())) {
+                    self.sell(merchant, index);
+                }
+            }
+        }
+    }
+
+    fn merchant_shop(&mut self, merchant: EntityId) -> Option<&mut Shop> {
+        self.entities.get_mut(merchant)?.shop.as_mut()
+    }
+
+    fn buy(&mut self, merchant: EntityId, index: usize) {
+        let Some(item) = self.merchant_shop(merchant).and_then(|s| s.stock.get(index)) else { return };
+        let (name, price) = (item.name, shop::buy_price(item));
+        if self.player.gold < price {
+            self.log.add(format!("You can't afford the {}.", name), GRAY);
+            return;
+        }
+        if self.player.inventory.is_full() {
+            self.log.add("Your pack is full.", GRAY);
+            return;
+        }
+        let Some(item) = self.merchant_shop(merchant).map(|s| s.stock.remove(index)) else { return };
+        self.player.gold -= price;
+        let _ = self.player.inventory.add(item);
+        self.log.add(format!("You buy the {} for {} gold.", name, price), GOLD);
+    }
+
+    fn sell(&mut self, merchant: EntityId, index: usize) {
+        let Some(item) = self.player.inventory.remove(index) else { return };
+        let price = shop::sell_price(&item);
+        self.player.gold += price;
+        self.log.add(format!("You sell the {} for {} gold.", item.name, price), GOLD);
+        // Sold goods go on the shelf until the next restock
+        if let Some(shop) = self.merchant_shop(merchant) {
+            shop.stock.push(item);
+        }
+    }
+
+    pub(super) fn render_shop(&self) {
+        let UiMode::Shop(merchant, action) = self.mode else { return };
+        let Some(entity) = self.entities.get(merchant) else { return };
+        let Some(shop) = &entity.shop else { return };
+
+        let (title, items, price): (String, &[Item], fn(&Item) -> i32) = match action {
+            TradeAction::Buy => (format!("Buy from the {}", entity.name), &shop.stock, shop::buy_price),
+            TradeAction::Sell => (format!("Sell to the {}", entity.name), &self.player.inventory.items, shop::sell_price),
+        };
+        let footer = format!("You have {} gold - Tab to switch, Esc to leave", self.player.gold);
+        if items.is_empty() {
+            ui::draw_panel(&title, &["Nothing to trade.".to_string()], &footer);
+        } else {
+            let lines: Vec<String> = items.iter().map(|i| format!("{} - {} gold", i.name, price(i))).collect();
+            ui::draw_menu(&title, &lines, &footer);
+        }
+    }
+}
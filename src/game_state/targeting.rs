@@ -0,0 +1,183 @@
+// targeting.rs
+//
+// Aiming and resolving ranged attacks: firing the wielded weapon or throwing
+// something from the pack.
+use macroquad::prelude::*;
+use crate::combat::{self, AttackResult, Fighter};
+use crate::entity::EntityId;
+use crate::equipment::EquipSlot;
+use crate::game_state::{GameState, Shot, UiMode, CHAR_HEIGHT, CHAR_WIDTH};
+use crate::geometry::Pos;
+use crate::item::{self, Ranged};
+use crate::noise::Noise;
+use crate::turn::ActionKind;
+
+impl GameState {
+    /// Hostiles the player can see, nearest first.
+    pub(super) fn visible_enemies(&self) -> Vec<EntityId> {
+        let in_sight = self.visible_tiles();
+        let mut enemies: Vec<_> = self
+            .entities
+            .in_radius(self.player.pos, self.player.sight_radius())
+            .filter(|e| self.is_hostile(e.id) && in_sight.contains(&e.pos))
+            .map(|e| (e.pos.distance_sq(self.player.pos), e.id))
+            .collect();
+        enemies.sort();
+        enemies.into_iter().map(|(_, id)| id).collect()
+    }
+
+    /// Aims at the nearest enemy in sight, if there is one.
+    fn start_targeting(&mut self, shot: Shot) {
+        match self.visible_enemies().first() {
+            Some(&target) => self.mode = UiMode::Targeting(shot, target),
+            None => self.log.add("There is nothing in sight to shoot at.", GRAY),
+        }
+    }
+
+    pub(super) fn start_fire(&mut self) {
+        if self.missile(Shot::Fire).is_none() {
+            self.log.add("You aren't wielding anything that shoots.", GRAY);
+            return;
+        }
+        self.start_targeting(Shot::Fire);
+    }
+
+    /// Aims the inventory item at `index`, if it can be thrown.
+    pub(super) fn start_throw(&mut self, index: usize) {
+        let Some(item) = self.player.inventory.items.get(index) else { return };
+        if item.ranged.is_none() {
+            self.log.add(format!("The {} isn't made for throwing.", item.name), GRAY);
+            return;
+        }
+        self.start_targeting(Shot::Throw(index));
+    }
+
+    /// Tab cycles through enemies in sight; F or Enter lets fly.
+    pub(super) fn targeting_input(&mut self, shot: Shot, target: EntityId) -> Option<ActionKind> {
+        if is_key_pressed(KeyCode::Escape) {
+            self.mode = UiMode::Normal;
+            return None;
+        }
+        let enemies = self.visible_enemies();
+        let current = enemies.iter().position(|id| *id == target);
+        if current.is_none() || is_key_pressed(KeyCode::Tab) {
+            // The old target may have wandered out of sight
+            let next = current.map_or(0, |i| i + 1);
+            match enemies.get(next % enemies.len().max(1)) {
+                Some(&next) => self.mode = UiMode::Targeting(shot, next),
+                None => self.mode = UiMode::Normal,
+            }
+            return None;
+        }
+        if is_key_pressed(KeyCode::F) || is_key_pressed(KeyCode::Enter) {
+            self.mode = UiMode::Normal;
+            return self.shoot(shot, target);
+        }
+        None
+    }
+
+    /// What a shot sends flying: its name in messages and how it does.
+    fn missile(&self, shot: Shot) -> Option<(&'static str, Ranged)> {
+        match shot {
+            Shot::Fire => {
+                let weapon = self.player.equipment.get(EquipSlot::Weapon)?;
+                Some(("shot", weapon.ranged?))
+            }
+            Shot::Throw(index) => {
+                let item = self.player.inventory.items.get(index)?;
+                Some((item.name, item.ranged?))
+            }
+        }
+    }
+
+    /// The tiles a projectile from the player towards `to` crosses, and the
+    /// blocking entity it stops at, if any. It flies at most `range` tiles
+    /// and drops in front of the first wall.
+    fn projectile_path(&self, to: Pos, range: i32) -> (Vec<Pos>, Option<EntityId>) {
+        let mut path = Vec::new();
+        for pos in self.map.cast_ray(self.player.pos, to).path.into_iter().take(range.max(0) as usize) {
+            if !self.map.get_tile(pos).is_walkable() {
+                break;
+            }
+            path.push(pos);
+            if let Some(id) = self.entities.blocker_at(pos) {
+                return (path, Some(id));
+            }
+        }
+        (path, None)
+    }
+
+    fn shoot(&mut self, shot: Shot, target: EntityId) -> Option<ActionKind> {
+        let to = self.entities.get(target)?.pos;
+        let (name, ranged) = self.missile(shot)?;
+        let (path, hit) = self.projectile_path(to, ranged.range);
+
+        // Thrown things land wherever they stop
+        let landing = path.last().copied().unwrap_or(self.player.pos);
+        if let Shot::Throw(index) = shot
+            && let Some(item) = self.player.inventory.remove(index)
+        {
+            self.entities.spawn(item::on_floor(item, landing));
+        }
+
+        match hit {
+            Some(id) if self.is_hostile(id) => {
+                self.ranged_attack(id, ranged.damage);
+                return Some(ActionKind::Shoot);
+            }
+            Some(id) => {
+                let victim = self.entities.get(id).map(|e| e.name.clone()).unwrap_or_default();
+                self.log.add(format!("Your {} thuds into the {}.", name, victim), GRAY);
+            }
+            None => self.log.add(format!("Your {} falls short.", name), GRAY),
+        }
+        // A clatter in the distance can draw monsters away from the player
+        self.make_noise(landing, Noise::Impact.loudness());
+        Some(ActionKind::Shoot)
+    }
+
+    /// Rolls a projectile hit against `target`, using the player's own aim.
+    fn ranged_attack(&mut self, target: EntityId, damage: i32) {
+        let Some(entity) = self.entities.get_mut(target) else { return };
+        let Some(fighter) = entity.fighter.as_mut() else { return };
+
+        let attacker = Fighter { attack: damage, ..self.player.fighter };
+        let result = combat::attack(&attacker, fighter);
+        let msg = combat::describe("You", &format!("the {}", entity.name), result);
+        let pos = entity.pos;
+        self.log.add(msg, WHITE);
+        self.make_noise(pos, Noise::Combat.loudness());
+
+        if let AttackResult::Kill { .. } = result {
+            self.player_kill(target);
+        }
+    }
+
+    /// Draws the projectile's path and a box round the target.
+    pub(super) fn render_targeting(&self) {
+        let UiMode::Targeting(shot, target) = self.mode else { return };
+        let Some(entity) = self.entities.get(target) else { return };
+        let Some((_, ranged)) = self.missile(shot) else { return };
+
+        let viewport = self.viewport();
+        let (path, hit) = self.projectile_path(entity.pos, ranged.range);
+        let reaches = hit == Some(target);
+        for pos in &path {
+            if *pos != entity.pos {
+                self.draw_glyph(viewport, *pos, '*', if reaches { YELLOW } else { ORANGE });
+            }
+        }
+        if viewport.contains(entity.pos) {
+            let Pos { x, y } = entity.pos - viewport.top_left();
+            let color = if reaches { YELLOW } else { RED };
+            draw_rectangle_lines(x as f32 * CHAR_WIDTH, y as f32 * CHAR_HEIGHT, CHAR_WIDTH, CHAR_HEIGHT, 2.0, color);
+        }
+
+        let hint = format!(
+            "Aiming at the {}{} - Tab next target, F or Enter to shoot, Esc to cancel",
+            entity.name,
+            if reaches { "" } else { " (blocked or out of range)" }
+        );
+        draw_text(&hint, 10.0, 16.0, 18.0, YELLOW);
+    }
+}
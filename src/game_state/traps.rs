@@ -0,0 +1,129 @@
+// traps.rs
+//
+// Finding hidden features, by searching or in passing, and springing traps
+// the player steps on.
+use macroquad::prelude::*;
+use crate::game_state::GameState;
+use crate::geometry::Pos;
+use crate::map::chunk::{chunk_coords, CHUNK_SIZE};
+use crate::map::tile::Tile;
+use crate::trap::{self, Feature, Trap, SEARCH_RADIUS};
+use crate::turn::ActionKind;
+
+/// Damage a dart does before the danger level is added.
+const DART_DAMAGE: (i32, i32) = (1, 4);
+/// Damage from falling into a pit.
+const FALL_DAMAGE: (i32, i32) = (2, 6);
+/// How loud an alarm is; see `noise::Noise::loudness`.
+const ALARM_LOUDNESS: i32 = 25;
+/// Furthest a teleport trap sends the player on each axis.
+const TELEPORT_RANGE: i32 = 20;
+
+impl GameState {
+    /// Looks over the tiles around the player for hidden things.
+    pub(super) fn search(&mut self) -> ActionKind {
+        let chance = trap::search_chance(self.player.search());
+        if !self.look_for_hidden(chance) {
+            self.log.add("You search the area but find nothing.", GRAY);
+        }
+        ActionKind::Search
+    }
+
+    /// The player's passive chance of spotting hidden things near them,
+    /// rolled at the end of each of their turns.
+    pub(super) fn notice_hidden(&mut self) {
+        self.look_for_hidden(trap::notice_chance(self.player.search()));
+    }
+
+    /// Rolls `chance` for each unfound feature in sight within search range.
+    /// Returns whether anything turned up.
+    fn look_for_hidden(&mut self, chance: i32) -> bool {
+        let in_sight = self.visible_tiles();
+        let near: Vec<Pos> = in_sight
+            .into_iter()
+            .filter(|p| p.chebyshev(self.player.pos) <= SEARCH_RADIUS)
+            .filter(|p| self.map.hidden_at(*p).is_some_and(|h| !h.found))
+            .collect();
+        let mut found = false;
+        for pos in near {
+            if rand::gen_range(0, 100) >= chance {
+                continue;
+            }
+            match self.map.reveal(pos) {
+                Some(Feature::SecretDoor) => self.log.add("You find a secret door!", SKYBLUE),
+                Some(Feature::Trap(trap)) => self.log.add(format!("You find a {}!", trap.name()), SKYBLUE),
+                None => continue,
+            }
+            found = true;
+        }
+        found
+    }
+
+    /// Springs the trap at `pos`, if there is one, on the player who just
+    /// stepped there. Found traps still go off; they're just not a surprise.
+    pub(super) fn spring_trap(&mut self, pos: Pos) {
+        let Some(Feature::Trap(trap)) = self.map.reveal(pos) else { return };
+        let level = self.map.chunk_at(pos).map_or(1, |c| c.danger_level());
+        match trap {
+            Trap::Dart => {
+                let damage = rand::gen_range(DART_DAMAGE.0, DART_DAMAGE.1 + 1) + level;
+                self.player.fighter.hp -= damage;
+                self.log.add(format!("A dart shoots out of the wall! ({} damage)", damage), RED);
+                if self.player.fighter.is_dead() {
+                    self.kill_player();
+                }
+            }
+            Trap::Pit => self.fall_into_pit(pos),
+            Trap::Alarm => {
+                self.log.add("A bell clangs loudly somewhere nearby!", ORANGE);
+                self.make_noise(pos, ALARM_LOUDNESS);
+            }
+            Trap::Teleport => {
+                let dest = pos
+                    + Pos::new(
+                        rand::gen_range(-TELEPORT_RANGE, TELEPORT_RANGE + 1),
+                        rand::gen_range(-TELEPORT_RANGE, TELEPORT_RANGE + 1),
+                    );
+                self.log.add("The floor flashes and the world lurches around you!", VIOLET);
+                self.land_player_near(dest);
+            }
+        }
+    }
+
+    /// Drops the player into the next ring of chunks out, where the danger
+    /// level is one higher: the nearest thing this world has to a level
+    /// below.
+    fn fall_into_pit(&mut self, pos: Pos) {
+        let (chunk_x, chunk_y) = chunk_coords(pos);
+        let (down_x, down_y) = match (chunk_x, chunk_y) {
+            (0, 0) => (1, 0),
+            (x, y) if x.abs() >= y.abs() => (x + x.signum(), y),
+            (x, y) => (x, y + y.signum()),
+        };
+        let damage = rand::gen_range(FALL_DAMAGE.0, FALL_DAMAGE.1 + 1);
+        self.player.fighter.hp -= damage;
+        self.log.add(format!("The floor gives way and you fall into the depths! ({} damage)", damage), RED);
+        if self.player.fighter.is_dead() {
+            self.kill_player();
+            return;
+        }
+        let centre = Pos::new(down_x * CHUNK_SIZE + CHUNK_SIZE / 2, down_y * CHUNK_SIZE + CHUNK_SIZE / 2);
+        self.land_player_near(centre);
+    }
+
+    /// Moves the player to the free floor tile nearest `target`, loading the
+    /// chunks around it first. Stays put if there's nowhere to land.
+    fn land_player_near(&mut self, target: Pos) {
+        self.load_chunks_around(target);
+        let free = |p: Pos, tile: &Tile| {
+            tile.is_walkable() && self.entities.blocker_at(p).is_none() && self.map.hidden_at(p).is_none()
+        };
+        let Some(dest) = self.map.find_nearest(target, CHUNK_SIZE, free) else {
+            self.load_chunks_around(self.player.pos);
+            return;
+        };
+        self.load_chunks_around(dest);
+        self.player.pos = dest;
+        self.visit_chunk(dest);
+    }
+}
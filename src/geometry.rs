@@ -0,0 +1,282 @@
+// geometry.rs
+//
+// Grid geometry shared by map generation and gameplay: positions,
+// directions, rectangles and shape iterators. Nothing here knows about
+// tiles or chunks.
+
+use std::ops::{Add, AddAssign, Mul, Neg, Sub};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Pos {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Pos {
+    pub fn new(x: i32, y: i32) -> Self {
+        Self { x, y }
+    }
+
+    /// Taxicab distance: the number of orthogonal steps between two points.
+    pub fn manhattan(&self, other: Pos) -> i32 {
+        (self.x - other.x).abs() + (self.y - other.y).abs()
+    }
+
+    /// King-move distance: the number of 8-way steps between two points.
+    pub fn chebyshev(&self, other: Pos) -> i32 {
+        (self.x - other.x).abs().max((self.y - other.y).abs())
+    }
+
+    /// Squared straight-line distance, for comparisons without a sqrt.
+    pub fn distance_sq(&self, other: Pos) -> i32 {
+        let dx = self.x - other.x;
+        let dy = self.y - other.y;
+        dx * dx + dy * dy
+    }
+}
+
+impl Add for Pos {
+    type Output = Pos;
+
+    fn add(self, rhs: Pos) -> Pos {
+        Pos::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl Sub for Pos {
+    type Output = Pos;
+
+    fn sub(self, rhs: Pos) -> Pos {
+        Pos::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+impl Neg for Pos {
+    type Output = Pos;
+
+    fn neg(self) -> Pos {
+        Pos::new(-self.x, -self.y)
+    }
+}
+
+impl Mul<i32> for Pos {
+    type Output = Pos;
+
+    fn mul(self, rhs: i32) -> Pos {
+        Pos::new(self.x * rhs, self.y * rhs)
+    }
+}
+
+impl Add<Direction> for Pos {
+    type Output = Pos;
+
+    fn add(self, rhs: Direction) -> Pos {
+        self + rhs.delta()
+    }
+}
+
+impl AddAssign<Direction> for Pos {
+    fn add_assign(&mut self, rhs: Direction) {
+        *self = *self + rhs;
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Direction {
+    North,
+    NorthEast,
+    East,
+    SouthEast,
+    South,
+    SouthWest,
+    West,
+    NorthWest,
+}
+
+impl Direction {
+    /// The four orthogonal directions, in N, S, W, E order.
+    pub const CARDINALS: [Direction; 4] = [
+        Direction::North,
+        Direction::South,
+        Direction::West,
+        Direction::East,
+    ];
+
+    /// All eight directions, clockwise from north.
+    pub const ALL: [Direction; 8] = [
+        Direction::North,
+        Direction::NorthEast,
+        Direction::East,
+        Direction::SouthEast,
+        Direction::South,
+        Direction::SouthWest,
+        Direction::West,
+        Direction::NorthWest,
+    ];
+
+    /// Unit step for this direction. North is -y, matching screen space.
+    pub fn delta(self) -> Pos {
+        match self {
+            Direction::North => Pos::new(0, -1),
+            Direction::NorthEast => Pos::new(1, -1),
+            Direction::East => Pos::new(1, 0),
+            Direction::SouthEast => Pos::new(1, 1),
+            Direction::South => Pos::new(0, 1),
+            Direction::SouthWest => Pos::new(-1, 1),
+            Direction::West => Pos::new(-1, 0),
+            Direction::NorthWest => Pos::new(-1, -1),
+        }
+    }
+
+    /// The direction whose step matches the signs of `(dx, dy)`, or `None`
+    /// for a zero vector.
+    pub fn from_delta(dx: i32, dy: i32) -> Option<Direction> {
+        let step = Pos::new(dx.signum(), dy.signum());
+        Self::ALL.into_iter().find(|d| d.delta() == step)
+    }
+}
+
+/// Axis-aligned rectangle of tiles. `x`/`y` is the top-left corner and the
+/// rectangle covers `width * height` tiles.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Rect {
+    pub fn new(x: i32, y: i32, width: i32, height: i32) -> Self {
+        Self { x, y, width, height }
+    }
+
+    /// A square of side `2 * radius + 1` centred on `center`.
+    pub fn around(center: Pos, radius: i32) -> Self {
+        Self::new(center.x - radius, center.y - radius, radius * 2 + 1, radius * 2 + 1)
+    }
+
+    pub fn top_left(&self) -> Pos {
+        Pos::new(self.x, self.y)
+    }
+
+    pub fn contains(&self, pos: Pos) -> bool {
+        pos.x >= self.x && pos.x < self.x + self.width && pos.y >= self.y && pos.y < self.y + self.height
+    }
+
+    /// Every position in the rectangle, row by row.
+    pub fn positions(&self) -> impl Iterator<Item = Pos> + use<> {
+        let Rect { x, y, width, height } = *self;
+        (y..y + height).flat_map(move |py| (x..x + width).map(move |px| Pos::new(px, py)))
+    }
+}
+
+/// Bresenham line from `from` to `to`, both ends included.
+pub fn line(from: Pos, to: Pos) -> Line {
+    let dx = (to.x - from.x).abs();
+    let dy = -(to.y - from.y).abs();
+    Line {
+        curr: from,
+        to,
+        dx,
+        dy,
+        sx: (to.x - from.x).signum(),
+        sy: (to.y - from.y).signum(),
+        err: dx + dy,
+        done: false,
+    }
+}
+
+pub struct Line {
+    curr: Pos,
+    to: Pos,
+    dx: i32,
+    dy: i32,
+    sx: i32,
+    sy: i32,
+    err: i32,
+    done: bool,
+}
+
+impl Iterator for Line {
+    type Item = Pos;
+
+    fn next(&mut self) -> Option<Pos> {
+        if self.done {
+            return None;
+        }
+        let out = self.curr;
+        if self.curr == self.to {
+            self.done = true;
+            return Some(out);
+        }
+
+        let e2 = 2 * self.err;
+        if e2 >= self.dy {
+            self.err += self.dy;
+            self.curr.x += self.sx;
+        }
+        if e2 <= self.dx {
+            self.err += self.dx;
+            self.curr.y += self.sy;
+        }
+        Some(out)
+    }
+}
+
+/// Every position whose centre lies within `radius` of `center` (a filled
+/// disc), row by row.
+pub fn circle(center: Pos, radius: i32) -> impl Iterator<Item = Pos> {
+    let r2 = radius * radius;
+    Rect::around(center, radius)
+        .positions()
+        .filter(move |p| p.distance_sq(center) <= r2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_distance_metrics() {
+        let a = Pos::new(0, 0);
+        let b = Pos::new(3, -4);
+        assert_eq!(a.manhattan(b), 7);
+        assert_eq!(a.chebyshev(b), 4);
+        assert_eq!(a.distance_sq(b), 25);
+    }
+
+    #[test]
+    fn test_direction_round_trip() {
+        for dir in Direction::ALL {
+            let d = dir.delta();
+            assert_eq!(Direction::from_delta(d.x, d.y), Some(dir));
+        }
+        assert_eq!(Direction::from_delta(0, 0), None);
+        assert_eq!(Direction::from_delta(5, -2), Some(Direction::NorthEast));
+    }
+
+    #[test]
+    fn test_line_endpoints() {
+        let points: Vec<Pos> = line(Pos::new(0, 0), Pos::new(5, 2)).collect();
+        assert_eq!(points.first(), Some(&Pos::new(0, 0)));
+        assert_eq!(points.last(), Some(&Pos::new(5, 2)));
+        assert_eq!(points.len(), 6);
+        // Consecutive points are always adjacent
+        for w in points.windows(2) {
+            assert_eq!(w[0].chebyshev(w[1]), 1);
+        }
+        assert_eq!(line(Pos::new(3, 3), Pos::new(3, 3)).count(), 1);
+    }
+
+    #[test]
+    fn test_rect_and_circle() {
+        let r = Rect::new(-2, -2, 4, 3);
+        assert_eq!(r.positions().count(), 12);
+        assert!(r.contains(Pos::new(1, 0)));
+        assert!(!r.contains(Pos::new(2, 0)));
+
+        assert_eq!(circle(Pos::new(0, 0), 0).count(), 1);
+        assert_eq!(circle(Pos::new(10, 10), 1).count(), 5);
+    }
+}
@@ -0,0 +1,122 @@
+// hunger.rs
+//
+// The food clock. Satiation drains by one every turn the player takes and
+// food tops it up; running out makes the player weak and then starving,
+// which slowly kills them.
+use crate::equipment::StatModifiers;
+
+/// Most satiation the player can hold.
+pub const MAX_SATIATION: i32 = 2000;
+/// Satiation at the start of a game.
+pub const START_SATIATION: i32 = 1500;
+/// Turns between each point of damage while starving.
+const STARVATION_TURNS: u32 = 10;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum HungerState {
+    Starving,
+    Weak,
+    Hungry,
+    Fed,
+    Full,
+}
+
+impl HungerState {
+    fn from_satiation(satiation: i32) -> Self {
+        match satiation {
+            s if s <= 0 => HungerState::Starving,
+            s if s <= 150 => HungerState::Weak,
+            s if s <= 500 => HungerState::Hungry,
+            s if s < 1600 => HungerState::Fed,
+            _ => HungerState::Full,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            HungerState::Starving => "Starving",
+            HungerState::Weak => "Weak",
+            HungerState::Hungry => "Hungry",
+            HungerState::Fed => "Not hungry",
+            HungerState::Full => "Full",
+        }
+    }
+
+    /// Combat penalty for going without food.
+    pub fn modifiers(self) -> StatModifiers {
+        match self {
+            HungerState::Starving | HungerState::Weak => StatModifiers { attack: -2, defense: -1, ..StatModifiers::NONE },
+            _ => StatModifiers::NONE,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Hunger {
+    pub satiation: i32,
+    /// Turns counted towards the next point of starvation damage.
+    starving_for: u32,
+}
+
+impl Hunger {
+    pub fn new() -> Self {
+        Self {
+            satiation: START_SATIATION,
+            starving_for: 0,
+        }
+    }
+
+    pub fn state(&self) -> HungerState {
+        HungerState::from_satiation(self.satiation)
+    }
+
+    /// Drains a turn's worth of satiation. Returns the HP lost to
+    /// starvation, if any.
+    pub fn on_turn(&mut self) -> i32 {
+        self.satiation = (self.satiation - 1).max(0);
+        if self.state() != HungerState::Starving {
+            self.starving_for = 0;
+            return 0;
+        }
+        self.starving_for += 1;
+        if self.starving_for >= STARVATION_TURNS {
+            self.starving_for = 0;
+            return 1;
+        }
+        0
+    }
+
+    pub fn eat(&mut self, nutrition: i32) {
+        self.satiation = (self.satiation + nutrition).min(MAX_SATIATION);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hunger_states() {
+        let mut hunger = Hunger::new();
+        assert_eq!(hunger.state(), HungerState::Fed);
+        hunger.satiation = 501;
+        hunger.on_turn();
+        assert_eq!(hunger.state(), HungerState::Hungry);
+        hunger.satiation = 1;
+        hunger.on_turn();
+        assert_eq!(hunger.state(), HungerState::Starving);
+        assert_eq!(hunger.state().modifiers().attack, -2);
+
+        hunger.eat(MAX_SATIATION * 2);
+        assert_eq!(hunger.satiation, MAX_SATIATION);
+        assert_eq!(hunger.state(), HungerState::Full);
+    }
+
+    #[test]
+    fn test_starvation_damage() {
+        let mut hunger = Hunger::new();
+        hunger.satiation = 0;
+        let lost: i32 = (0..STARVATION_TURNS * 3).map(|_| hunger.on_turn()).sum();
+        assert_eq!(lost, 3);
+    }
+}
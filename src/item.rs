@@ -0,0 +1,383 @@
+// item.rs
+//
+// Item templates, the item component carried by entities lying on the floor,
+// and the player's inventory.
+use macroquad::prelude::*;
+use crate::entity::Entity;
+use crate::equipment::{EquipSlot, StatModifiers};
+use crate::geometry::Pos;
+use crate::status::{StatusEffect, StatusKind};
+
+/// What happens when an item is used from the inventory.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UseEffect {
+    Heal(i32),
+    /// Puts a status effect on the user.
+    Apply(StatusEffect),
+    /// Food: restores this much satiation.
+    Eat(i32),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ItemKind {
+    /// Used up when applied.
+    Consumable(UseEffect),
+    /// Worn or wielded in a slot for a stat bonus.
+    Equipment(EquipSlot, StatModifiers),
+    /// A pile of coins, which goes straight into the purse when picked up.
+    Gold(i32),
+    /// Only good for throwing.
+    Missile,
+    /// Reading it starts the quest with this id.
+    Note(&'static str),
+    /// Only good for crafting.
+    Material,
+}
+
+/// How an item does as a projectile. A wielded weapon with this shoots
+/// without being used up; anything else with it can be thrown.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Ranged {
+    /// Furthest it flies, in tiles.
+    pub range: i32,
+    pub damage: i32,
+}
+
+#[derive(Clone, Debug)]
+pub struct Item {
+    /// Template id, enough to rebuild the item when a chunk is reloaded.
+    pub id: &'static str,
+    pub name: &'static str,
+    pub glyph: char,
+    pub color: Color,
+    pub kind: ItemKind,
+    /// Worth in gold; shops buy and sell relative to this.
+    pub value: i32,
+    pub ranged: Option<Ranged>,
+}
+
+pub struct ItemTemplate {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub glyph: char,
+    pub color: Color,
+    pub kind: ItemKind,
+    pub value: i32,
+    pub ranged: Option<Ranged>,
+}
+
+pub const ITEMS: &[ItemTemplate] = &[
+    ItemTemplate {
+        id: "healing_potion",
+        name: "healing potion",
+        glyph: '!',
+        color: PINK,
+        kind: ItemKind::Consumable(UseEffect::Heal(12)),
+        value: 20,
+        ranged: None,
+    },
+    ItemTemplate {
+        id: "bandage",
+        name: "bandage",
+        glyph: '~',
+        color: WHITE,
+        kind: ItemKind::Consumable(UseEffect::Heal(5)),
+        value: 6,
+        ranged: None,
+    },
+    ItemTemplate {
+        id: "potion_of_speed",
+        name: "potion of speed",
+        glyph: '!',
+        color: YELLOW,
+        kind: ItemKind::Consumable(UseEffect::Apply(StatusEffect::new(StatusKind::Hasted, 20, 0))),
+        value: 30,
+        ranged: None,
+    },
+    ItemTemplate {
+        id: "potion_of_regeneration",
+        name: "potion of regeneration",
+        glyph: '!',
+        color: GREEN,
+        kind: ItemKind::Consumable(UseEffect::Apply(StatusEffect::new(StatusKind::Regenerating, 15, 1))),
+        value: 25,
+        ranged: None,
+    },
+    ItemTemplate {
+        id: "strange_mushroom",
+        name: "strange mushroom",
+        glyph: ',',
+        color: MAGENTA,
+        kind: ItemKind::Consumable(UseEffect::Apply(StatusEffect::new(StatusKind::Confused, 8, 0))),
+        value: 4,
+        ranged: None,
+    },
+    ItemTemplate {
+        id: "ration",
+        name: "ration",
+        glyph: ':',
+        color: BEIGE,
+        kind: ItemKind::Consumable(UseEffect::Eat(800)),
+        value: 8,
+        ranged: None,
+    },
+    ItemTemplate {
+        id: "apple",
+        name: "apple",
+        glyph: ',',
+        color: RED,
+        kind: ItemKind::Consumable(UseEffect::Eat(250)),
+        value: 2,
+        ranged: None,
+    },
+    ItemTemplate {
+        id: "dagger",
+        name: "dagger",
+        glyph: ')',
+        color: LIGHTGRAY,
+        kind: ItemKind::Equipment(EquipSlot::Weapon, StatModifiers { attack: 2, ..StatModifiers::NONE }),
+        value: 15,
+        ranged: Some(Ranged { range: 4, damage: 3 }),
+    },
+    ItemTemplate {
+        id: "sword",
+        name: "sword",
+        glyph: ')',
+        color: SKYBLUE,
+        kind: ItemKind::Equipment(EquipSlot::Weapon, StatModifiers { attack: 4, ..StatModifiers::NONE }),
+        value: 40,
+        ranged: None,
+    },
+    ItemTemplate {
+        id: "shortbow",
+        name: "shortbow",
+        glyph: '}',
+        color: BROWN,
+        kind: ItemKind::Equipment(EquipSlot::Weapon, StatModifiers::NONE),
+        value: 35,
+        ranged: Some(Ranged { range: 8, damage: 5 }),
+    },
+    ItemTemplate {
+        id: "dart",
+        name: "dart",
+        glyph: '(',
+        color: LIGHTGRAY,
+        kind: ItemKind::Missile,
+        value: 3,
+        ranged: Some(Ranged { range: 6, damage: 3 }),
+    },
+    ItemTemplate {
+        id: "leather_armour",
+        name: "leather armour",
+        glyph: '[',
+        color: BROWN,
+        kind: ItemKind::Equipment(EquipSlot::Armour, StatModifiers { defense: 1, ..StatModifiers::NONE }),
+        value: 15,
+        ranged: None,
+    },
+    ItemTemplate {
+        id: "chain_mail",
+        name: "chain mail",
+        glyph: '[',
+        color: LIGHTGRAY,
+        kind: ItemKind::Equipment(EquipSlot::Armour, StatModifiers { defense: 3, ..StatModifiers::NONE }),
+        value: 50,
+        ranged: None,
+    },
+    ItemTemplate {
+        id: "ring_of_sight",
+        name: "ring of far sight",
+        glyph: '=',
+        color: GOLD,
+        kind: ItemKind::Equipment(EquipSlot::Ring, StatModifiers { fov_radius: 3, ..StatModifiers::NONE }),
+        value: 60,
+        ranged: None,
+    },
+    ItemTemplate {
+        id: "ring_of_protection",
+        name: "ring of protection",
+        glyph: '=',
+        color: VIOLET,
+        kind: ItemKind::Equipment(EquipSlot::Ring, StatModifiers { defense: 1, ..StatModifiers::NONE }),
+        value: 45,
+        ranged: None,
+    },
+    ItemTemplate {
+        id: "torch",
+        name: "torch",
+        glyph: '/',
+        color: ORANGE,
+        kind: ItemKind::Equipment(EquipSlot::Light, StatModifiers { light_radius: 3, ..StatModifiers::NONE }),
+        value: 10,
+        ranged: None,
+    },
+    ItemTemplate {
+        id: "old_map",
+        name: "old map",
+        glyph: '?',
+        color: BEIGE,
+        kind: ItemKind::Note("lost_camp"),
+        value: 5,
+        ranged: None,
+    },
+    ItemTemplate {
+        id: "explorers_journal",
+        name: "explorer's journal",
+        glyph: '?',
+        color: SKYBLUE,
+        kind: ItemKind::Note("far_horizons"),
+        value: 5,
+        ranged: None,
+    },
+    ItemTemplate {
+        id: "hide",
+        name: "hide",
+        glyph: '&',
+        color: BROWN,
+        kind: ItemKind::Material,
+        value: 4,
+        ranged: None,
+    },
+    ItemTemplate {
+        id: "spider_silk",
+        name: "spider silk",
+        glyph: '&',
+        color: WHITE,
+        kind: ItemKind::Material,
+        value: 5,
+        ranged: None,
+    },
+    ItemTemplate {
+        id: "bone",
+        name: "bone",
+        glyph: '&',
+        color: BEIGE,
+        kind: ItemKind::Material,
+        value: 2,
+        ranged: None,
+    },
+    ItemTemplate {
+        id: "iron_ore",
+        name: "iron ore",
+        glyph: '*',
+        color: ORANGE,
+        kind: ItemKind::Material,
+        value: 6,
+        ranged: None,
+    },
+    ItemTemplate {
+        id: "herb",
+        name: "herb",
+        glyph: '"',
+        color: GREEN,
+        kind: ItemKind::Material,
+        value: 3,
+        ranged: None,
+    },
+];
+
+pub fn template(id: &str) -> Option<&'static ItemTemplate> {
+    ITEMS.iter().find(|t| t.id == id)
+}
+
+/// A fresh copy of the item with the given template id.
+pub fn make(id: &str) -> Option<Item> {
+    let t = template(id)?;
+    Some(Item {
+        id: t.id,
+        name: t.name,
+        glyph: t.glyph,
+        color: t.color,
+        kind: t.kind,
+        value: t.value,
+        ranged: t.ranged,
+    })
+}
+
+/// Template id written for gold piles in saved chunks. Gold isn't in
+/// `ITEMS` because every pile holds a different amount.
+pub const GOLD_ID: &str = "gold";
+
+/// A pile of `amount` gold coins.
+pub fn gold(amount: i32) -> Item {
+    Item {
+        id: GOLD_ID,
+        name: "gold coins",
+        glyph: '$',
+        color: GOLD,
+        kind: ItemKind::Gold(amount),
+        value: amount,
+        ranged: None,
+    }
+}
+
+/// Builds an entity holding `item`, lying on the floor at `pos`.
+pub fn on_floor(item: Item, pos: Pos) -> Entity {
+    Entity::new(pos, item.glyph, item.color, item.name).with_item(item)
+}
+
+/// Carried items, in pickup order.
+pub struct Inventory {
+    pub items: Vec<Item>,
+    pub capacity: usize,
+}
+
+impl Inventory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            items: Vec::new(),
+            capacity,
+        }
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.items.len() >= self.capacity
+    }
+
+    /// Adds an item, handing it back if there's no room.
+    pub fn add(&mut self, item: Item) -> Result<(), Item> {
+        if self.is_full() {
+            return Err(item);
+        }
+        self.items.push(item);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, index: usize) -> Option<Item> {
+        (index < self.items.len()).then(|| self.items.remove(index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inventory_capacity() {
+        let mut inv = Inventory::new(2);
+        assert!(inv.add(make("bandage").unwrap()).is_ok());
+        assert!(inv.add(make("healing_potion").unwrap()).is_ok());
+        assert!(inv.is_full());
+
+        let rejected = inv.add(make("bandage").unwrap());
+        assert_eq!(rejected.map_err(|i| i.name), Err("bandage"));
+
+        assert_eq!(inv.remove(0).map(|i| i.name), Some("bandage"));
+        assert!(inv.remove(5).is_none());
+        assert!(!inv.is_full());
+    }
+
+    #[test]
+    fn test_ranged_items() {
+        let bow = make("shortbow").unwrap();
+        assert!(matches!(bow.kind, ItemKind::Equipment(EquipSlot::Weapon, _)));
+        assert_eq!(bow.ranged, Some(Ranged { range: 8, damage: 5 }));
+        assert_eq!(make("dart").map(|d| d.kind), Some(ItemKind::Missile));
+        assert_eq!(make("bandage").unwrap().ranged, None);
+    }
+
+    #[test]
+    fn test_unknown_template() {
+        assert!(make("no_such_item").is_none());
+    }
+}
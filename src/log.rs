@@ -0,0 +1,31 @@
+// log.rs
+use std::collections::VecDeque;
+use macroquad::prelude::Color;
+
+/// How many messages are kept for display.
+const LOG_CAPACITY: usize = 50;
+
+/// Scrolling message log shown under the map.
+pub struct MessageLog {
+    messages: VecDeque<(String, Color)>,
+}
+
+impl MessageLog {
+    pub fn new() -> Self {
+        Self {
+            messages: VecDeque::new(),
+        }
+    }
+
+    pub fn add(&mut self, text: impl Into<String>, color: Color) {
+        if self.messages.len() == LOG_CAPACITY {
+            self.messages.pop_front();
+        }
+        self.messages.push_back((text.into(), color));
+    }
+
+    /// The most recent `n` messages, oldest first.
+    pub fn recent(&self, n: usize) -> impl Iterator<Item = &(String, Color)> {
+        self.messages.iter().skip(self.messages.len().saturating_sub(n))
+    }
+}
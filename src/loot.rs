@@ -0,0 +1,290 @@
+// loot.rs
+//
+// Weighted loot and spawn tables, loaded from `data/loot.txt`. A table makes
+// some number of weighted picks and also hands out its guaranteed drops;
+// either can point at another table, which is rolled in turn. Every entry
+// can be limited to a range of danger levels.
+use std::collections::HashMap;
+use macroquad::rand::RandGenerator;
+use crate::data::{self, DataError, Field, Section};
+use crate::item;
+use crate::monster;
+
+const EMBEDDED: &str = include_str!("../data/loot.txt");
+/// Tables nested deeper than this are ignored, in case of a cycle.
+const MAX_DEPTH: usize = 8;
+
+/// Something a table produced, by template id.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Loot {
+    Item(&'static str),
+    Monster(&'static str),
+    Gold(i32),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Outcome {
+    Loot(Loot),
+    /// An amount of gold, rolled from an inclusive range.
+    Gold(i32, i32),
+    Table(String),
+    Nothing,
+}
+
+#[derive(Clone, Debug)]
+struct Entry {
+    outcome: Outcome,
+    weight: i32,
+    /// Inclusive range of levels this entry is available at.
+    levels: (i32, i32),
+}
+
+impl Entry {
+    fn allowed_at(&self, level: i32) -> bool {
+        (self.levels.0..=self.levels.1).contains(&level)
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Table {
+    /// Inclusive range of how many weighted picks to make.
+    rolls: (i32, i32),
+    picks: Vec<Entry>,
+    always: Vec<Entry>,
+}
+
+pub struct LootTables {
+    tables: HashMap<String, Table>,
+}
+
+impl LootTables {
+    /// Loads `data/loot.txt`, falling back to the built-in copy. Also returns
+    /// a warning if the file on disk had to be ignored.
+    pub fn load() -> (Self, Option<String>) {
+        data::load("loot.txt", EMBEDDED, Self::from_sections)
+    }
+
+    pub fn from_sections(sections: &[Section]) -> Result<Self, DataError> {
+        let mut tables = HashMap::new();
+        for section in sections {
+            if section.kind != "table" {
+                return Err(section.error(format!("unknown section kind '{}'", section.kind)));
+            }
+            if tables.insert(section.name.clone(), parse_table(section)?).is_some() {
+                return Err(section.error(format!("table '{}' is defined twice", section.name)));
+            }
+        }
+
+        // Every nested table must exist
+        for section in sections {
+            for field in &section.fields {
+                if let Ok(Some(entry)) = parse_entry(field)
+                    && let Outcome::Table(name) = &entry.outcome
+                    && !tables.contains_key(name)
+                {
+                    return Err(field.error(format!("no table called '{}'", name)));
+                }
+            }
+        }
+
+        Ok(Self { tables })
+    }
+
+    /// Rolls the named table at the given danger level. Unknown tables
+    /// produce nothing.
+    pub fn roll(&self, name: &str, level: i32, rng: &RandGenerator) -> Vec<Loot> {
+        let mut loot = Vec::new();
+        self.roll_into(name, level, rng, 0, &mut loot);
+        loot
+    }
+
+    fn roll_into(&self, name: &str, level: i32, rng: &RandGenerator, depth: usize, loot: &mut Vec<Loot>) {
+        let Some(table) = self.tables.get(name) else { return };
+        if depth > MAX_DEPTH {
+            return;
+        }
+
+        for entry in table.always.iter().filter(|e| e.allowed_at(level)) {
+            self.resolve(&entry.outcome, level, rng, depth, loot);
+        }
+
+        let eligible: Vec<&Entry> = table.picks.iter().filter(|e| e.allowed_at(level)).collect();
+        let total: i32 = eligible.iter().map(|e| e.weight).sum();
+        if total <= 0 {
+            return;
+        }
+        for _ in 0..rng.gen_range(table.rolls.0, table.rolls.1 + 1) {
+            let mut roll = rng.gen_range(0, total);
+            for entry in &eligible {
+                if roll < entry.weight {
+                    self.resolve(&entry.outcome, level, rng, depth, loot);
+                    break;
+                }
+                roll -= entry.weight;
+            }
+        }
+    }
+
+    fn resolve(&self, outcome: &Outcome, level: i32, rng: &RandGenerator, depth: usize, loot: &mut Vec<Loot>) {
+        match outcome {
+            Outcome::Loot(l) => loot.push(l.clone()),
+            Outcome::Gold(low, high) => loot.push(Loot::Gold(rng.gen_range(*low, *high + 1))),
+            Outcome::Table(name) => self.roll_into(name, level, rng, depth + 1, loot),
+            Outcome::Nothing => {}
+        }
+    }
+}
+
+fn parse_table(section: &Section) -> Result<Table, DataError> {
+    let rolls = match section.get("rolls") {
+        Some(field) => data::parse_range(&field.value)
+            .filter(|(low, high)| *low >= 0 && *high < i32::MAX)
+            .ok_or_else(|| field.error("rolls should look like 1-3"))?,
+        None => (1, 1),
+    };
+
+    let mut table = Table {
+        rolls,
+        picks: Vec::new(),
+        always: Vec::new(),
+    };
+    for field in &section.fields {
+        match parse_entry(field)? {
+            Some(entry) if field.key == "always" => table.always.push(entry),
+            Some(entry) => table.picks.push(entry),
+            None => {}
+        }
+    }
+    Ok(table)
+}
+
+/// Parses one table line. Returns `None` for fields that aren't entries.
+///
+/// Weighted picks are `item|monster|table = <weight> <id>`, `gold =
+/// <weight> <range>` or `nothing = <weight>`; guaranteed drops are `always =
+/// item|monster|table <id>` or `always = gold <range>`. Any of them can end
+/// in `level <range>`.
+fn parse_entry(field: &Field) -> Result<Option<Entry>, DataError> {
+    let mut words: Vec<&str> = field.value.split_whitespace().collect();
+
+    let mut levels = (i32::MIN, i32::MAX);
+    if let Some(at) = words.iter().position(|w| *w == "level") {
+        let range = words.get(at + 1).filter(|_| words.len() == at + 2);
+        levels = range
+            .and_then(|r| data::parse_range(r))
+            .ok_or_else(|| field.error("expected 'level <range>' at the end"))?;
+        words.truncate(at);
+    }
+
+    let (kind, weight, id) = match (field.key.as_str(), words.as_slice()) {
+        ("rolls", _) => return Ok(None),
+        ("nothing", [weight]) => ("nothing", *weight, ""),
+        ("item" | "monster" | "table" | "gold", [weight, id]) => (field.key.as_str(), *weight, *id),
+        ("always", [kind, id]) => (*kind, "1", *id),
+        (key @ ("nothing" | "item" | "monster" | "table" | "gold" | "always"), _) => {
+            return Err(field.error(format!("malformed '{}' entry", key)));
+        }
+        (key, _) => return Err(field.error(format!("unknown key '{}'", key))),
+    };
+
+    let weight: i32 = weight
+        .parse()
+        .ok()
+        .filter(|w| *w > 0)
+        .ok_or_else(|| field.error("weight should be a positive number"))?;
+
+    let outcome = match kind {
+        "nothing" => Outcome::Nothing,
+        "table" => Outcome::Table(id.to_string()),
+        "gold" => {
+            let (low, high) = data::parse_range(id)
+                .filter(|(low, high)| *low >= 0 && *high < i32::MAX)
+                .ok_or_else(|| field.error("gold should be a range like 5-10"))?;
+            Outcome::Gold(low, high)
+        }
+        "item" => Outcome::Loot(Loot::Item(
+            item::template(id).ok_or_else(|| field.error(format!("no item called '{}'", id)))?.id,
+        )),
+        "monster" => Outcome::Loot(Loot::Monster(
+            monster::template(id).ok_or_else(|| field.error(format!("no monster called '{}'", id)))?.id,
+        )),
+        _ => return Err(field.error("always should be followed by item, monster, gold or table")),
+    };
+
+    Ok(Some(Entry { outcome, weight, levels }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tables(text: &str) -> LootTables {
+        LootTables::from_sections(&data::parse(text).unwrap()).unwrap()
+    }
+
+    fn rng(seed: u64) -> RandGenerator {
+        let rng = RandGenerator::new();
+        rng.srand(seed);
+        rng
+    }
+
+    #[test]
+    fn test_builtin_tables_are_valid() {
+        let sections = data::parse(EMBEDDED).unwrap();
+        let loot = LootTables::from_sections(&sections).unwrap();
+        assert!(loot.tables.contains_key("chunk_monsters"));
+        assert!(loot.tables.contains_key("chunk_items"));
+    }
+
+    #[test]
+    fn test_nesting_and_guaranteed_drops() {
+        let loot = tables(
+            "[table outer]\nrolls = 2\ntable = 1 inner\nalways = item torch\n\
+             [table inner]\nitem = 1 dagger\n",
+        );
+        let drops = loot.roll("outer", 1, &rng(1));
+        assert_eq!(drops, vec![Loot::Item("torch"), Loot::Item("dagger"), Loot::Item("dagger")]);
+    }
+
+    #[test]
+    fn test_gold_amounts() {
+        let loot = tables("[table t]\nrolls = 0\nalways = gold 5-10\n");
+        for seed in 0..20 {
+            let drops = loot.roll("t", 1, &rng(seed));
+            assert!(matches!(drops[..], [Loot::Gold(5..=10)]), "{:?}", drops);
+        }
+    }
+
+    #[test]
+    fn test_level_ranges() {
+        let loot = tables("[table t]\nitem = 1 dagger level -2\nitem = 1 sword level 3-\n");
+        for seed in 0..20 {
+            assert_eq!(loot.roll("t", 1, &rng(seed)), vec![Loot::Item("dagger")]);
+            assert_eq!(loot.roll("t", 5, &rng(seed)), vec![Loot::Item("sword")]);
+        }
+    }
+
+    #[test]
+    fn test_same_seed_same_loot() {
+        let loot = tables("[table t]\nrolls = 1-5\nitem = 3 dagger\nitem = 2 sword\nnothing = 1\n");
+        for seed in 0..20 {
+            assert_eq!(loot.roll("t", 1, &rng(seed)), loot.roll("t", 1, &rng(seed)));
+        }
+    }
+
+    #[test]
+    fn test_rejects_bad_entries() {
+        let bad = [
+            "[table t]\nitem = 1 no_such_item\n",
+            "[table t]\nitem = 0 dagger\n",
+            "[table t]\ntable = 1 missing\n",
+            "[table t]\nitem = 1 dagger level\n",
+            "[table t]\ncolour = red\n",
+            "[table t]\nrolls = 3-\nitem = 1 dagger\n",
+            "[table t]\nrolls = 3-1\nitem = 1 dagger\n",
+        ];
+        for text in bad {
+            assert!(LootTables::from_sections(&data::parse(text).unwrap()).is_err(), "{}", text);
+        }
+    }
+}
@@ -1,25 +1,58 @@
 // main.rs
 use macroquad::prelude::*;
 
+mod ability;
+mod ai;
+mod combat;
+mod companion;
+mod crafting;
+mod data;
+mod dialogue;
 mod map;
 mod entity;
+mod equipment;
+mod faction;
+mod geometry;
+mod hunger;
+mod item;
+mod log;
+mod loot;
 mod game_state;
+mod monster;
+mod noise;
+mod progression;
+mod quest;
+mod shop;
+mod status;
+mod trap;
+mod turn;
+mod ui;
 
-use game_state::GameState;
+use game_state::{GameState, UiMode};
 
 #[macroquad::main("Roguelike")]
 async fn main() {
     macroquad::rand::srand(macroquad::miniquad::date::now() as u64);
-    let mut game = GameState::new();
+    // An optional world seed on the command line replays the same world
+    let seed = std::env::args().nth(1).and_then(|s| s.parse().ok()).unwrap_or_else(new_seed);
+    let mut game = GameState::new(seed);
 
     loop {
+        // Escape backs out of menus first and only quits from the map
+        if is_key_pressed(KeyCode::Escape) && game.mode == UiMode::Normal {
+            break;
+        }
+
         game.update_player();
         game.render();
 
-        if is_key_pressed(KeyCode::Escape) {
-            break;
+        if game.game_over && is_key_pressed(KeyCode::R) {
+            game = GameState::new(new_seed());
         }
 
         next_frame().await;
     }
-}
\ No newline at end of file
+}
+fn new_seed() -> u64 {
+    ((macroquad::rand::rand() as u64) << 32) | macroquad::rand::rand() as u64
+}
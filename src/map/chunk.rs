@@ -1,28 +1,58 @@
+use std::collections::HashMap;
 use macroquad::prelude::*;
+use macroquad::rand::RandGenerator;
+use crate::geometry::{Pos, Rect};
 use crate::map::tile::Tile;
+use crate::trap::Hidden;
 
 pub const CHUNK_SIZE: i32 = 32;
 
-#[derive(Clone)]
 pub struct Chunk {
     pub x: i32,
     pub y: i32,
     pub tiles: Vec<Tile>, // Flattened 2D array [y * CHUNK_SIZE + x]
+    /// Traps and secret doors, by local position. The tile underneath
+    /// shows what they pass for until they're found.
+    pub hidden: HashMap<Pos, Hidden>,
+    /// Random numbers for everything rolled in this chunk (terrain, spawns,
+    /// loot), seeded from the world seed and the chunk's coordinates so the
+    /// same seed always rolls the same way.
+    pub rng: RandGenerator,
 }
 
 impl Chunk {
-    pub fn new(x: i32, y: i32) -> Self {
+    pub fn new(x: i32, y: i32, world_seed: u64) -> Self {
         let size = (CHUNK_SIZE * CHUNK_SIZE) as usize;
         let tiles = vec![Tile::wall(); size]; // Start full of walls
+        let rng = RandGenerator::new();
+        rng.srand(chunk_seed(world_seed, x, y));
         Self {
             x,
             y,
             tiles,
+            hidden: HashMap::new(),
+            rng,
         }
     }
 
+    /// A seed to carry on the chunk's rolls from once it's reloaded, taken
+    /// from its RNG so it doesn't replay the rolls it has already made.
+    pub fn next_seed(&self) -> u64 {
+        ((self.rng.rand() as u64) << 32) | self.rng.rand() as u64
+    }
+
+    /// How dangerous this chunk is; see `danger_level`.
+    pub fn danger_level(&self) -> i32 {
+        danger_level(self.x, self.y)
+    }
+
+    /// The world-space area this chunk covers.
+    pub fn bounds(&self) -> Rect {
+        Rect::new(self.x * CHUNK_SIZE, self.y * CHUNK_SIZE, CHUNK_SIZE, CHUNK_SIZE)
+    }
+
     pub fn get_tile(&self, local_x: i32, local_y: i32) -> Option<&Tile> {
-        if local_x < 0 || local_x >= CHUNK_SIZE || local_y < 0 || local_y >= CHUNK_SIZE {
+        if !(0..CHUNK_SIZE).contains(&local_x) || !(0..CHUNK_SIZE).contains(&local_y) {
             return None;
         }
         let idx = (local_y * CHUNK_SIZE + local_x) as usize;
@@ -30,10 +60,32 @@ impl Chunk {
     }
 
     pub fn set_tile(&mut self, local_x: i32, local_y: i32, tile: Tile) {
-        if local_x < 0 || local_x >= CHUNK_SIZE || local_y < 0 || local_y >= CHUNK_SIZE {
+        if !(0..CHUNK_SIZE).contains(&local_x) || !(0..CHUNK_SIZE).contains(&local_y) {
             return;
         }
         let idx = (local_y * CHUNK_SIZE + local_x) as usize;
         self.tiles[idx] = tile;
     }
 }
+
+/// Coordinates of the chunk containing a world position.
+pub fn chunk_coords(pos: Pos) -> (i32, i32) {
+    (pos.x.div_euclid(CHUNK_SIZE), pos.y.div_euclid(CHUNK_SIZE))
+}
+
+/// Difficulty of a chunk, from 1 at the start upwards. Each ring of chunks
+/// around (0, 0) is one level more dangerous than the ring inside it; this
+/// scales monster stats and picks which loot table entries are available.
+pub fn danger_level(chunk_x: i32, chunk_y: i32) -> i32 {
+    1 + chunk_x.abs().max(chunk_y.abs())
+}
+
+/// Mixes the world seed with chunk coordinates (splitmix64) so neighbouring
+/// chunks get unrelated random streams.
+fn chunk_seed(world_seed: u64, x: i32, y: i32) -> u64 {
+    let coords = ((x as u32 as u64) << 32) | y as u32 as u64;
+    let mut z = world_seed ^ coords.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
@@ -1,25 +1,65 @@
 pub mod tile;
 pub mod chunk;
+pub mod query;
+pub mod save;
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod tests;
 
 use std::collections::{HashMap, HashSet, VecDeque};
-use macroquad::rand; // Import macroquad's rand
-use crate::map::chunk::{Chunk, CHUNK_SIZE};
+use crate::entity::Entity;
+use crate::map::chunk::{chunk_coords, Chunk, CHUNK_SIZE};
 use crate::map::tile::Tile;
-use crate::entity::Pos;
+use crate::geometry::{Direction, Pos};
+use crate::trap::{Feature, Hidden, Trap};
+
+/// Percent chance each corridor tile gets a door.
+const DOOR_CHANCE: i32 = 4;
+/// Percent chance each wall tile facing open floor holds an ore vein.
+const ORE_CHANCE: i32 = 3;
+/// Percent chance each floor tile grows herbs.
+const HERB_CHANCE: i32 = 1;
+/// Chance in a thousand each floor tile hides a trap, outside the starting
+/// ring of chunks.
+const TRAP_CHANCE: i32 = 6;
+/// Traps by weight.
+const TRAPS: [(Trap, i32); 4] = [(Trap::Dart, 4), (Trap::Alarm, 3), (Trap::Teleport, 2), (Trap::Pit, 1)];
+/// Percent chance each thin wall between two passages hides a secret door.
+const SECRET_DOOR_CHANCE: i32 = 10;
 
 pub struct MapManager {
     pub chunks: HashMap<(i32, i32), Chunk>,
+    /// Unloaded chunks and their entities, serialized by `save::save_chunk`.
+    pub saved: HashMap<(i32, i32), String>,
+    /// World seed each chunk's RNG is derived from.
+    pub seed: u64,
+}
+
+/// What `generate_chunk_if_needed` had to do.
+#[derive(Default)]
+pub struct ChunkLoad {
+    /// A brand new chunk was generated and still needs populating.
+    pub generated: bool,
+    /// Entities saved with chunks that were brought back, ready to respawn.
+    pub restored: Vec<Entity>,
+    /// Saved chunks that failed to load and were generated afresh instead.
+    pub warnings: Vec<String>,
 }
 
 impl MapManager {
-    pub fn new() -> Self {
+    pub fn new(seed: u64) -> Self {
         Self {
             chunks: HashMap::new(),
+            saved: HashMap::new(),
+            seed,
         }
     }
 
+    /// The loaded chunk containing a world position.
+    pub fn chunk_at(&self, pos: Pos) -> Option<&Chunk> {
+        self.chunks.get(&chunk_coords(pos))
+    }
+
     pub fn get_tile(&self, pos: Pos) -> Tile {
         let chunk_x = pos.x.div_euclid(CHUNK_SIZE);
         let chunk_y = pos.y.div_euclid(CHUNK_SIZE);
@@ -27,19 +67,96 @@ impl MapManager {
         let local_x = pos.x.rem_euclid(CHUNK_SIZE);
         let local_y = pos.y.rem_euclid(CHUNK_SIZE);
 
-        if let Some(chunk) = self.chunks.get(&(chunk_x, chunk_y)) {
-            if let Some(tile) = chunk.get_tile(local_x, local_y) {
-                return *tile;
-            }
+        if let Some(chunk) = self.chunks.get(&(chunk_x, chunk_y))
+            && let Some(tile) = chunk.get_tile(local_x, local_y)
+        {
+            return *tile;
         }
 
         Tile::wall()
     }
 
-    pub fn generate_chunk_if_needed(&mut self, chunk_x: i32, chunk_y: i32) {
+    /// Overwrites a single tile. Does nothing if the chunk isn't loaded.
+    pub fn set_tile(&mut self, pos: Pos, tile: Tile) {
+        let chunk_x = pos.x.div_euclid(CHUNK_SIZE);
+        let chunk_y = pos.y.div_euclid(CHUNK_SIZE);
+
+        if let Some(chunk) = self.chunks.get_mut(&(chunk_x, chunk_y)) {
+            chunk.set_tile(pos.x.rem_euclid(CHUNK_SIZE), pos.y.rem_euclid(CHUNK_SIZE), tile);
+        }
+    }
+
+    /// The hidden feature at a world position, found or not.
+    pub fn hidden_at(&self, pos: Pos) -> Option<Hidden> {
+        let (local_x, local_y) = (pos.x.rem_euclid(CHUNK_SIZE), pos.y.rem_euclid(CHUNK_SIZE));
+        self.chunk_at(pos)?.hidden.get(&Pos::new(local_x, local_y)).copied()
+    }
+
+    /// Marks the feature at `pos` as found. A secret door turns into an
+    /// ordinary one and stops being hidden at all.
+    pub fn reveal(&mut self, pos: Pos) -> Option<Feature> {
+        let local = Pos::new(pos.x.rem_euclid(CHUNK_SIZE), pos.y.rem_euclid(CHUNK_SIZE));
+        let chunk = self.chunks.get_mut(&chunk_coords(pos))?;
+        let hidden = chunk.hidden.get_mut(&local)?;
+        hidden.found = true;
+        let feature = hidden.feature;
+        if feature == Feature::SecretDoor {
+            chunk.hidden.remove(&local);
+            // Unless something already dug through it
+            if chunk.get_tile(local.x, local.y).is_some_and(|t| !t.is_walkable()) {
+                chunk.set_tile(local.x, local.y, Tile::door());
+            }
+        }
+        Some(feature)
+    }
+
+    /// Serializes a chunk together with the entities standing in it and drops
+    /// it from memory. It comes back the next time it's needed. Returns a
+    /// warning for each entity that couldn't be saved.
+    pub fn unload_chunk(&mut self, chunk_x: i32, chunk_y: i32, entities: &[Entity]) -> Vec<String> {
+        let Some(chunk) = self.chunks.remove(&(chunk_x, chunk_y)) else { return Vec::new() };
+        let (text, warnings) = save::save_chunk(&chunk, entities);
+        self.saved.insert((chunk_x, chunk_y), text);
+        warnings
+    }
+
+    /// Brings back an unloaded chunk into `load`. If it won't load it's
+    /// forgotten, with a warning, so it gets generated afresh. Returns
+    /// whether it came back.
+    fn restore_chunk(&mut self, key: (i32, i32), load: &mut ChunkLoad) -> bool {
+        let Some(text) = self.saved.remove(&key) else { return false };
+        match save::load_chunk(&text, self.seed) {
+            Ok((chunk, entities)) => {
+                self.chunks.insert(key, chunk);
+                load.restored.extend(entities);
+                true
+            }
+            Err(e) => {
+                load.warnings.push(format!("Chunk {},{} failed to reload ({}); generating it afresh.", key.0, key.1, e));
+                false
+            }
+        }
+    }
+
+    /// Makes sure the chunk is loaded: restores it if it was unloaded, or
+    /// generates it if it has never existed.
+    pub fn generate_chunk_if_needed(&mut self, chunk_x: i32, chunk_y: i32) -> ChunkLoad {
+        let mut load = ChunkLoad::default();
         if self.chunks.contains_key(&(chunk_x, chunk_y)) {
-            return;
+            return load;
+        }
+        if self.restore_chunk((chunk_x, chunk_y), &mut load) {
+            return load;
+        }
+
+        // A new chunk has to line up with the edges of its neighbours, so
+        // bring back any that were unloaded
+        for dir in Direction::CARDINALS {
+            let d = dir.delta();
+            let key = (chunk_x + d.x, chunk_y + d.y);
+            self.restore_chunk(key, &mut load);
         }
+        load.generated = true;
 
         let mut connections = Vec::new();
 
@@ -47,7 +164,7 @@ impl MapManager {
         // North
         if let Some(north) = self.chunks.get(&(chunk_x, chunk_y - 1)) {
             for x in 0..CHUNK_SIZE {
-                if north.get_tile(x, CHUNK_SIZE - 1).map_or(false, |t| t.char == '.') {
+                if north.get_tile(x, CHUNK_SIZE - 1).is_some_and(|t| t.char == '.') {
                     connections.push(Pos::new(x, 0));
                 }
             }
@@ -55,7 +172,7 @@ impl MapManager {
         // South
         if let Some(south) = self.chunks.get(&(chunk_x, chunk_y + 1)) {
             for x in 0..CHUNK_SIZE {
-                if south.get_tile(x, 0).map_or(false, |t| t.char == '.') {
+                if south.get_tile(x, 0).is_some_and(|t| t.char == '.') {
                     connections.push(Pos::new(x, CHUNK_SIZE - 1));
                 }
             }
@@ -63,7 +180,7 @@ impl MapManager {
         // West
         if let Some(west) = self.chunks.get(&(chunk_x - 1, chunk_y)) {
             for y in 0..CHUNK_SIZE {
-                if west.get_tile(CHUNK_SIZE - 1, y).map_or(false, |t| t.char == '.') {
+                if west.get_tile(CHUNK_SIZE - 1, y).is_some_and(|t| t.char == '.') {
                     connections.push(Pos::new(0, y));
                 }
             }
@@ -71,13 +188,13 @@ impl MapManager {
         // East
         if let Some(east) = self.chunks.get(&(chunk_x + 1, chunk_y)) {
             for y in 0..CHUNK_SIZE {
-                if east.get_tile(0, y).map_or(false, |t| t.char == '.') {
+                if east.get_tile(0, y).is_some_and(|t| t.char == '.') {
                     connections.push(Pos::new(CHUNK_SIZE - 1, y));
                 }
             }
         }
 
-        let mut chunk = Chunk::new(chunk_x, chunk_y);
+        let mut chunk = Chunk::new(chunk_x, chunk_y, self.seed);
 
         let mut walkers = connections.clone();
         if walkers.is_empty() {
@@ -94,19 +211,15 @@ impl MapManager {
             chunk.set_tile(curr.x, curr.y, Tile::floor());
 
             for _ in 0..steps_per_walker {
-                let dir = rand::gen_range(0, 4);
-                let (dx, dy) = match dir {
-                    0 => (0, -1), 1 => (0, 1), 2 => (-1, 0), 3 => (1, 0), _ => (0, 0),
-                };
-
-                let next_x = curr.x + dx;
-                let next_y = curr.y + dy;
-
-                if next_x >= 0 && next_x < CHUNK_SIZE && next_y >= 0 && next_y < CHUNK_SIZE {
-                    if self.is_move_allowed(&chunk, chunk_x, chunk_y, next_x, next_y) {
-                        curr = Pos::new(next_x, next_y);
-                        chunk.set_tile(curr.x, curr.y, Tile::floor());
-                    }
+                let dir = Direction::CARDINALS[chunk.rng.gen_range(0, 4)];
+                let next = curr + dir;
+
+                if (0..CHUNK_SIZE).contains(&next.x)
+                    && (0..CHUNK_SIZE).contains(&next.y)
+                    && self.is_move_allowed(&chunk, chunk_x, chunk_y, next.x, next.y)
+                {
+                    curr = next;
+                    chunk.set_tile(curr.x, curr.y, Tile::floor());
                 }
             }
         }
@@ -131,19 +244,15 @@ impl MapManager {
             }
 
             while let Some(pos) = queue.pop_front() {
-                for (dx, dy) in &[(0, 1), (0, -1), (1, 0), (-1, 0)] {
-                    let nx = pos.x + dx;
-                    let ny = pos.y + dy;
-                    if nx >= 0 && nx < CHUNK_SIZE && ny >= 0 && ny < CHUNK_SIZE {
-                        let n_pos = Pos::new(nx, ny);
-                        if !visited.contains(&n_pos) {
-                            if let Some(tile) = chunk.get_tile(nx, ny) {
-                                if tile.char == '.' {
-                                    visited.insert(n_pos);
-                                    queue.push_back(n_pos);
-                                }
-                            }
-                        }
+                for dir in Direction::CARDINALS {
+                    let n_pos = pos + dir;
+                    if (0..CHUNK_SIZE).contains(&n_pos.x)
+                        && (0..CHUNK_SIZE).contains(&n_pos.y)
+                        && !visited.contains(&n_pos)
+                        && chunk.get_tile(n_pos.x, n_pos.y).is_some_and(|t| t.char == '.')
+                    {
+                        visited.insert(n_pos);
+                        queue.push_back(n_pos);
                     }
                 }
             }
@@ -168,10 +277,10 @@ impl MapManager {
         }
 
         // --- 3. Fix Dead Ends (Force Exit) ---
-        let has_north_exit = !self.chunks.contains_key(&(chunk_x, chunk_y - 1)) && (0..CHUNK_SIZE).any(|x| chunk.get_tile(x, 0).map_or(false, |t| t.char == '.'));
-        let has_south_exit = !self.chunks.contains_key(&(chunk_x, chunk_y + 1)) && (0..CHUNK_SIZE).any(|x| chunk.get_tile(x, CHUNK_SIZE - 1).map_or(false, |t| t.char == '.'));
-        let has_west_exit = !self.chunks.contains_key(&(chunk_x - 1, chunk_y)) && (0..CHUNK_SIZE).any(|y| chunk.get_tile(0, y).map_or(false, |t| t.char == '.'));
-        let has_east_exit = !self.chunks.contains_key(&(chunk_x + 1, chunk_y)) && (0..CHUNK_SIZE).any(|y| chunk.get_tile(CHUNK_SIZE - 1, y).map_or(false, |t| t.char == '.'));
+        let has_north_exit = !self.chunks.contains_key(&(chunk_x, chunk_y - 1)) && (0..CHUNK_SIZE).any(|x| chunk.get_tile(x, 0).is_some_and(|t| t.char == '.'));
+        let has_south_exit = !self.chunks.contains_key(&(chunk_x, chunk_y + 1)) && (0..CHUNK_SIZE).any(|x| chunk.get_tile(x, CHUNK_SIZE - 1).is_some_and(|t| t.char == '.'));
+        let has_west_exit = !self.chunks.contains_key(&(chunk_x - 1, chunk_y)) && (0..CHUNK_SIZE).any(|y| chunk.get_tile(0, y).is_some_and(|t| t.char == '.'));
+        let has_east_exit = !self.chunks.contains_key(&(chunk_x + 1, chunk_y)) && (0..CHUNK_SIZE).any(|y| chunk.get_tile(CHUNK_SIZE - 1, y).is_some_and(|t| t.char == '.'));
 
         let has_any_exit = has_north_exit || has_south_exit || has_west_exit || has_east_exit;
 
@@ -183,21 +292,21 @@ impl MapManager {
             if !self.chunks.contains_key(&(chunk_x + 1, chunk_y)) { potential_edges.push(3); }
 
             if !potential_edges.is_empty() {
-                let target_idx = rand::gen_range(0, potential_edges.len());
+                let target_idx = chunk.rng.gen_range(0, potential_edges.len());
                 let target_edge = potential_edges[target_idx];
 
                 // Pick a random floor tile as start to ensure connectivity
                 let mut floor_tiles = Vec::new();
                 for y in 0..CHUNK_SIZE {
                     for x in 0..CHUNK_SIZE {
-                        if chunk.get_tile(x, y).map_or(false, |t| t.char == '.') {
+                        if chunk.get_tile(x, y).is_some_and(|t| t.char == '.') {
                             floor_tiles.push(Pos::new(x, y));
                         }
                     }
                 }
 
                 let start_pos = if !floor_tiles.is_empty() {
-                    floor_tiles[rand::gen_range(0, floor_tiles.len())]
+                    floor_tiles[chunk.rng.gen_range(0, floor_tiles.len())]
                 } else {
                     Pos::new(CHUNK_SIZE/2, CHUNK_SIZE/2)
                 };
@@ -215,33 +324,122 @@ impl MapManager {
             }
         }
 
+        // --- 4. Doors ---
+        Self::place_doors(&mut chunk);
+
+        // --- 5. Resources ---
+        Self::place_resources(&mut chunk);
+
+        // --- 6. Hidden features ---
+        Self::place_hidden(&mut chunk);
+
         self.chunks.insert((chunk_x, chunk_y), chunk);
+        load
+    }
+
+    /// Hangs doors in some of the one-tile-wide corridors. Edge tiles are
+    /// left alone so neighbouring chunks still line up.
+    fn place_doors(chunk: &mut Chunk) {
+        let is_floor = |chunk: &Chunk, x: i32, y: i32| chunk.get_tile(x, y).is_some_and(|t| t.char == '.');
+        for y in 1..CHUNK_SIZE - 1 {
+            for x in 1..CHUNK_SIZE - 1 {
+                if !is_floor(chunk, x, y) || chunk.rng.gen_range(0, 100) >= DOOR_CHANCE {
+                    continue;
+                }
+                let (north, south) = (is_floor(chunk, x, y - 1), is_floor(chunk, x, y + 1));
+                let (west, east) = (is_floor(chunk, x - 1, y), is_floor(chunk, x + 1, y));
+                let corridor = (north && south && !west && !east) || (west && east && !north && !south);
+                let beside_door = Direction::CARDINALS
+                    .iter()
+                    .any(|d| chunk.get_tile(x + d.delta().x, y + d.delta().y).is_some_and(|t| t.is_closed_door()));
+                if corridor && !beside_door {
+                    chunk.set_tile(x, y, Tile::door());
+                }
+            }
+        }
+    }
+
+    /// Seeds ore veins in walls the player can reach and herbs on the floor.
+    /// Like doors, they stay off the edges.
+    fn place_resources(chunk: &mut Chunk) {
+        let is_floor = |chunk: &Chunk, x: i32, y: i32| chunk.get_tile(x, y).is_some_and(|t| t.char == '.');
+        for y in 1..CHUNK_SIZE - 1 {
+            for x in 1..CHUNK_SIZE - 1 {
+                let roll = chunk.rng.gen_range(0, 100);
+                if is_floor(chunk, x, y) {
+                    if roll < HERB_CHANCE {
+                        chunk.set_tile(x, y, Tile::herbs());
+                    }
+                } else if chunk.get_tile(x, y).is_some_and(|t| t.char == '#')
+                    && roll < ORE_CHANCE
+                    && Direction::CARDINALS.iter().any(|d| is_floor(chunk, x + d.delta().x, y + d.delta().y))
+                {
+                    chunk.set_tile(x, y, Tile::ore_vein());
+                }
+            }
+        }
+    }
+
+    /// Hides secret doors in walls one tile thick between two passages, and
+    /// traps under the floor once the player is past the starting chunk.
+    fn place_hidden(chunk: &mut Chunk) {
+        let is_floor = |chunk: &Chunk, x: i32, y: i32| chunk.get_tile(x, y).is_some_and(|t| t.char == '.');
+        let traps = chunk.danger_level() > 1;
+        for y in 1..CHUNK_SIZE - 1 {
+            for x in 1..CHUNK_SIZE - 1 {
+                let Some(tile) = chunk.get_tile(x, y) else { continue };
+                if tile.char == '.' {
+                    if traps && chunk.rng.gen_range(0, 1000) < TRAP_CHANCE {
+                        let total: i32 = TRAPS.iter().map(|(_, w)| w).sum();
+                        let mut roll = chunk.rng.gen_range(0, total);
+                        let trap = TRAPS.iter().find(|(_, w)| {
+                            roll -= w;
+                            roll < 0
+                        });
+                        if let Some((trap, _)) = trap {
+                            chunk.hidden.insert(Pos::new(x, y), Hidden::new(Feature::Trap(*trap)));
+                        }
+                    }
+                } else if tile.char == '#' {
+                    let (north, south) = (is_floor(chunk, x, y - 1), is_floor(chunk, x, y + 1));
+                    let (west, east) = (is_floor(chunk, x - 1, y), is_floor(chunk, x + 1, y));
+                    let thin = (north && south && !west && !east) || (west && east && !north && !south);
+                    if thin && chunk.rng.gen_range(0, 100) < SECRET_DOOR_CHANCE {
+                        chunk.hidden.insert(Pos::new(x, y), Hidden::new(Feature::SecretDoor));
+                    }
+                }
+            }
+        }
     }
 
     fn is_move_allowed(&self, _chunk: &Chunk, chunk_x: i32, chunk_y: i32, next_x: i32, next_y: i32) -> bool {
         // North edge check
-        if next_y == 0 {
-            if let Some(north) = self.chunks.get(&(chunk_x, chunk_y - 1)) {
-                if north.get_tile(next_x, CHUNK_SIZE - 1).map_or(false, |t| t.char == '#') { return false; }
-            }
+        if next_y == 0
+            && let Some(north) = self.chunks.get(&(chunk_x, chunk_y - 1))
+            && north.get_tile(next_x, CHUNK_SIZE - 1).is_some_and(|t| t.char == '#')
+        {
+            return false;
         }
         // South edge check
-        if next_y == CHUNK_SIZE - 1 {
-            if let Some(south) = self.chunks.get(&(chunk_x, chunk_y + 1)) {
-                if south.get_tile(next_x, 0).map_or(false, |t| t.char == '#') { return false; }
-            }
+        if next_y == CHUNK_SIZE - 1
+            && let Some(south) = self.chunks.get(&(chunk_x, chunk_y + 1))
+            && south.get_tile(next_x, 0).is_some_and(|t| t.char == '#')
+        {
+            return false;
         }
         // West edge check
-        if next_x == 0 {
-            if let Some(west) = self.chunks.get(&(chunk_x - 1, chunk_y)) {
-                if west.get_tile(CHUNK_SIZE - 1, next_y).map_or(false, |t| t.char == '#') { return false; }
-            }
+        if next_x == 0
+            && let Some(west) = self.chunks.get(&(chunk_x - 1, chunk_y))
+            && west.get_tile(CHUNK_SIZE - 1, next_y).is_some_and(|t| t.char == '#')
+        {
+            return false;
         }
         // East edge check
-        if next_x == CHUNK_SIZE - 1 {
-            if let Some(east) = self.chunks.get(&(chunk_x + 1, chunk_y)) {
-                if east.get_tile(0, next_y).map_or(false, |t| t.char == '#') { return false; }
-            }
+        if next_x == CHUNK_SIZE - 1
+            && let Some(east) = self.chunks.get(&(chunk_x + 1, chunk_y))
+            && east.get_tile(0, next_y).is_some_and(|t| t.char == '#')
+        {
+            return false;
         }
         true
     }
@@ -258,66 +456,53 @@ impl MapManager {
 
             let move_x = dx.signum();
             let move_y = dy.signum();
+            let (primary, secondary) = if dx.abs() > dy.abs() {
+                (Pos::new(move_x, 0), Pos::new(0, move_y))
+            } else {
+                (Pos::new(0, move_y), Pos::new(move_x, 0))
+            };
 
             // Randomly choose whether to move along X or Y, with bias towards the larger distance
             // But also allow "mistakes" (moving perpendicular) to make it organic.
 
-            let r = rand::gen_range(0, 100);
+            let r = chunk.rng.gen_range(0, 100);
 
             let next_pos = if r < 70 {
                 // Move towards target
-                if dx.abs() > dy.abs() {
-                    Pos::new(curr.x + move_x, curr.y)
-                } else {
-                    Pos::new(curr.x, curr.y + move_y)
-                }
+                curr + primary
             } else if r < 85 {
                 // Move towards target (secondary axis)
-                if dx.abs() > dy.abs() {
-                    Pos::new(curr.x, curr.y + move_y)
-                } else {
-                     Pos::new(curr.x + move_x, curr.y)
-                }
+                curr + secondary
             } else {
                 // Move perpendicular / random wander
-                 let wander_dir = rand::gen_range(0, 4);
-                 match wander_dir {
-                     0 => Pos::new(curr.x, curr.y - 1),
-                     1 => Pos::new(curr.x, curr.y + 1),
-                     2 => Pos::new(curr.x - 1, curr.y),
-                     3 => Pos::new(curr.x + 1, curr.y),
-                     _ => curr
-                 }
+                curr + Direction::CARDINALS[chunk.rng.gen_range(0, 4)]
             };
 
             // Clamp and Check bounds
-            if next_pos.x >= 0 && next_pos.x < CHUNK_SIZE && next_pos.y >= 0 && next_pos.y < CHUNK_SIZE {
+            if (0..CHUNK_SIZE).contains(&next_pos.x) && (0..CHUNK_SIZE).contains(&next_pos.y) {
                 // Ensure we don't violate boundary consistency!
                 if self.is_move_allowed(chunk, chunk_x, chunk_y, next_pos.x, next_pos.y) {
                     curr = next_pos;
                     chunk.set_tile(curr.x, curr.y, Tile::floor());
                 } else {
                     // blocked by wall neighbor, force valid move towards target to avoid infinite loop
-                     if dx.abs() > dy.abs() {
-                        curr.x += move_x;
-                    } else {
-                        curr.y += move_y;
-                    }
+                    curr = curr + primary;
                     // If even the forced move is blocked, we just break/stop or accept it?
                     // If we are forcing a path, we usually want to succeed.
                     // But if strict boundary is required, we can't overwrite the wall.
                     // The 'target' should be valid (an empty edge).
                     // So we should eventually reach it.
-                    if curr.x >= 0 && curr.x < CHUNK_SIZE && curr.y >= 0 && curr.y < CHUNK_SIZE {
-                        if self.is_move_allowed(chunk, chunk_x, chunk_y, curr.x, curr.y) {
-                            chunk.set_tile(curr.x, curr.y, Tile::floor());
-                        }
+                    if (0..CHUNK_SIZE).contains(&curr.x)
+                        && (0..CHUNK_SIZE).contains(&curr.y)
+                        && self.is_move_allowed(chunk, chunk_x, chunk_y, curr.x, curr.y)
+                    {
+                        chunk.set_tile(curr.x, curr.y, Tile::floor());
                     }
                 }
             }
 
             // Safety break if we get stuck
-            if (curr.x - target.x).abs() + (curr.y - target.y).abs() < 1 {
+            if curr.manhattan(target) < 1 {
                  chunk.set_tile(target.x, target.y, Tile::floor()); // Ensure target is floor
                  break;
             }
@@ -0,0 +1,174 @@
+// query.rs
+//
+// World-space queries over the chunked map. Everything here works in
+// absolute `Pos` coordinates and reads through `MapManager::get_tile`, so
+// searches cross chunk borders transparently. Tiles in chunks that have not
+// been generated read as walls.
+use std::collections::{HashMap, HashSet, VecDeque};
+use crate::geometry::{self, Direction, Pos, Rect};
+use crate::map::MapManager;
+use crate::map::tile::Tile;
+
+/// Result of casting a ray across the map.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Ray {
+    /// Tiles visited after the origin, up to and including the blocking tile.
+    pub path: Vec<Pos>,
+    /// The first non-walkable tile the ray ran into, if any.
+    pub blocked_at: Option<Pos>,
+}
+
+impl MapManager {
+    /// Finds the tile matching `pred` closest to `origin` in straight-line
+    /// distance, looking no further than `max_radius` along either axis.
+    pub fn find_nearest<F>(&self, origin: Pos, max_radius: i32, pred: F) -> Option<Pos>
+    where
+        F: Fn(Pos, &Tile) -> bool,
+    {
+        let mut best: Option<Pos> = None;
+        for r in 0..=max_radius {
+            // Every tile on this ring or beyond is at least r away, so a
+            // match closer than that can't be beaten.
+            if best.is_some_and(|b| b.distance_sq(origin) <= r * r) {
+                break;
+            }
+            // Only visit the outer ring, inner ones were already searched.
+            let ring = Rect::around(origin, r)
+                .positions()
+                .filter(|p| p.chebyshev(origin) == r)
+                .filter(|p| pred(*p, &self.get_tile(*p)));
+            best = best.into_iter().chain(ring).min_by_key(|p| p.distance_sq(origin));
+        }
+        best
+    }
+
+    /// Iterates over every tile in `rect`, row by row.
+    pub fn tiles_in_rect(&self, rect: Rect) -> impl Iterator<Item = (Pos, Tile)> + '_ {
+        rect.positions().map(|p| (p, self.get_tile(p)))
+    }
+
+    /// Iterates over every tile whose centre lies within `radius` of `center`.
+    pub fn tiles_in_radius(&self, center: Pos, radius: i32) -> impl Iterator<Item = (Pos, Tile)> + '_ {
+        geometry::circle(center, radius).map(|p| (p, self.get_tile(p)))
+    }
+
+    /// Collects the 4-connected region around `start` whose tiles match `pred`.
+    /// Stops after `limit` tiles, since an open region in an infinite world
+    /// has no natural end.
+    pub fn flood_fill<F>(&self, start: Pos, limit: usize, pred: F) -> HashSet<Pos>
+    where
+        F: Fn(Pos, &Tile) -> bool,
+    {
+        let mut region = HashSet::new();
+        if !pred(start, &self.get_tile(start)) {
+            return region;
+        }
+
+        let mut queue = VecDeque::new();
+        region.insert(start);
+        queue.push_back(start);
+
+        while let Some(pos) = queue.pop_front() {
+            for dir in Direction::CARDINALS {
+                if region.len() >= limit {
+                    return region;
+                }
+                let next = pos + dir;
+                if !region.contains(&next) && pred(next, &self.get_tile(next)) {
+                    region.insert(next);
+                    queue.push_back(next);
+                }
+            }
+        }
+        region
+    }
+
+    /// The shortest path from `from` to `to` in all eight directions over
+    /// tiles matching `passable`, not counting `from` itself. `to` counts as
+    /// passable even if it isn't, since it's usually someone's position.
+    /// Gives up after exploring `limit` tiles.
+    pub fn find_path<F>(&self, from: Pos, to: Pos, limit: usize, passable: F) -> Option<Vec<Pos>>
+    where
+        F: Fn(Pos, &Tile) -> bool,
+    {
+        let mut came_from = HashMap::from([(from, from)]);
+        let mut queue = VecDeque::from([from]);
+
+        while let Some(pos) = queue.pop_front() {
+            if pos == to {
+                let mut path = vec![pos];
+                let mut step = pos;
+                while came_from[&step] != from {
+                    step = came_from[&step];
+                    path.push(step);
+                }
+                path.reverse();
+                return Some(path);
+            }
+            if came_from.len() >= limit {
+                break;
+            }
+            for dir in Direction::ALL {
+                let next = pos + dir;
+                if !came_from.contains_key(&next) && (next == to || passable(next, &self.get_tile(next))) {
+                    came_from.insert(next, pos);
+                    queue.push_back(next);
+                }
+            }
+        }
+        None
+    }
+
+    /// How many steps through walkable tiles it takes to reach each tile
+    /// within `max_steps` of `origin`, which is how far a noise made there
+    /// has to travel. Walls and closed doors stop it; going round them costs
+    /// the extra steps.
+    pub fn noise_spread(&self, origin: Pos, max_steps: i32) -> HashMap<Pos, i32> {
+        let mut steps = HashMap::from([(origin, 0)]);
+        let mut queue = VecDeque::from([origin]);
+
+        while let Some(pos) = queue.pop_front() {
+            let next_steps = steps[&pos] + 1;
+            if next_steps > max_steps {
+                continue;
+            }
+            for dir in Direction::CARDINALS {
+                let next = pos + dir;
+                if !steps.contains_key(&next) && self.get_tile(next).is_walkable() {
+                    steps.insert(next, next_steps);
+                    queue.push_back(next);
+                }
+            }
+        }
+        steps
+    }
+
+    /// Walks a Bresenham line from `from` towards `to`, stopping at the first
+    /// tile that cannot be walked on.
+    pub fn cast_ray(&self, from: Pos, to: Pos) -> Ray {
+        let mut path = Vec::new();
+
+        for curr in geometry::line(from, to).skip(1) {
+            path.push(curr);
+            if !self.get_tile(curr).is_walkable() {
+                return Ray { path, blocked_at: Some(curr) };
+            }
+        }
+
+        Ray { path, blocked_at: None }
+    }
+
+    /// Every position within `radius` of `origin` that it has line of sight to.
+    pub fn field_of_view(&self, origin: Pos, radius: i32) -> HashSet<Pos> {
+        self.tiles_in_radius(origin, radius)
+            .map(|(p, _)| p)
+            .filter(|p| self.has_line_of_sight(origin, *p))
+            .collect()
+    }
+
+    /// True if nothing blocks the straight line between the two positions.
+    /// The target itself may be a wall: you can see the wall you're facing.
+    pub fn has_line_of_sight(&self, from: Pos, to: Pos) -> bool {
+        self.cast_ray(from, to).blocked_at.is_none_or(|p| p == to)
+    }
+}
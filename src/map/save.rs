@@ -0,0 +1,84 @@
+// save.rs
+//
+// Text form of an unloaded chunk: a `[chunk x,y]` section holding the tiles
+// one row per line, where its RNG had got to and any hidden features,
+// followed by a section for each entity standing in it.
+use std::fmt::Write;
+use crate::data::{self, DataError};
+use crate::entity::Entity;
+use crate::entity::save::{read_entity, write_entity};
+use crate::map::chunk::{Chunk, CHUNK_SIZE};
+use crate::geometry::Pos;
+use crate::map::tile::Tile;
+use crate::trap::{Feature, Hidden};
+
+/// Writes a chunk and the entities in it, along with a warning for each
+/// entity that had to be left out.
+pub fn save_chunk(chunk: &Chunk, entities: &[Entity]) -> (String, Vec<String>) {
+    let mut out = String::new();
+    let _ = writeln!(out, "[chunk {},{}]", chunk.x, chunk.y);
+    for row in chunk.tiles.chunks(CHUNK_SIZE as usize) {
+        let _ = writeln!(out, "row = {}", row.iter().map(|t| t.char).collect::<String>());
+    }
+    let _ = writeln!(out, "rng = {}", chunk.next_seed());
+    // Sorted so the same chunk always saves the same way
+    let mut hidden: Vec<_> = chunk.hidden.iter().collect();
+    hidden.sort_by_key(|(pos, _)| (pos.y, pos.x));
+    for (pos, h) in hidden {
+        let found = if h.found { " found" } else { "" };
+        let _ = writeln!(out, "hidden = {} {} {}{}", pos.x, pos.y, h.feature.id(), found);
+    }
+    let warnings = entities.iter().filter_map(|e| write_entity(e, &mut out).err()).collect();
+    (out, warnings)
+}
+
+/// Rebuilds a chunk and its entities from `save_chunk`'s output. The chunk's
+/// RNG carries on from where it was saved.
+pub fn load_chunk(text: &str, world_seed: u64) -> Result<(Chunk, Vec<Entity>), DataError> {
+    let sections = data::parse(text)?;
+    let Some((header, rest)) = sections.split_first().filter(|(h, _)| h.kind == "chunk") else {
+        return Err(DataError::new(1, "expected a [chunk x,y] section first"));
+    };
+
+    let coords = header.name.split_once(',').and_then(|(x, y)| Some((x.parse().ok()?, y.parse().ok()?)));
+    let Some((x, y)) = coords else {
+        return Err(header.error("chunk name should be x,y"));
+    };
+    let mut chunk = Chunk::new(x, y, world_seed);
+
+    let rows: Vec<_> = header.fields.iter().filter(|f| f.key == "row").collect();
+    if rows.len() != CHUNK_SIZE as usize {
+        return Err(header.error(format!("expected {} rows", CHUNK_SIZE)));
+    }
+    for (ty, row) in rows.iter().enumerate() {
+        if row.value.chars().count() != CHUNK_SIZE as usize {
+            return Err(row.error(format!("expected {} tiles", CHUNK_SIZE)));
+        }
+        for (tx, c) in row.value.chars().enumerate() {
+            let tile = Tile::from_char(c).ok_or_else(|| row.error(format!("unknown tile '{}'", c)))?;
+            chunk.set_tile(tx as i32, ty as i32, tile);
+        }
+    }
+
+    if let Some(field) = header.get("rng") {
+        let seed = field.value.parse().map_err(|_| field.error("rng should be a number"))?;
+        chunk.rng.srand(seed);
+    }
+
+    for field in header.fields.iter().filter(|f| f.key == "hidden") {
+        let words: Vec<&str> = field.value.split_whitespace().collect();
+        let (x, y, id, found) = match words.as_slice() {
+            [x, y, id] => (x, y, id, false),
+            [x, y, id, "found"] => (x, y, id, true),
+            _ => return Err(field.error("hidden should be '<x> <y> <feature> [found]'")),
+        };
+        let (Ok(x), Ok(y)) = (x.parse(), y.parse()) else {
+            return Err(field.error("hidden needs tile coordinates"));
+        };
+        let feature = Feature::from_id(id).ok_or_else(|| field.error(format!("unknown feature '{}'", id)))?;
+        chunk.hidden.insert(Pos::new(x, y), Hidden { feature, found });
+    }
+
+    let entities = rest.iter().map(read_entity).collect::<Result<_, _>>()?;
+    Ok((chunk, entities))
+}
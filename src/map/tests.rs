@@ -1,12 +1,20 @@
 #[cfg(test)]
 mod tests {
     use crate::map::MapManager;
-    use crate::map::chunk::CHUNK_SIZE;
-    use crate::entity::Pos;
+    use crate::map::chunk::{danger_level, Chunk, CHUNK_SIZE};
+    use crate::map::tile::Tile;
+    use crate::geometry::Pos;
+    use macroquad::prelude::{BEIGE, GOLD};
+    use crate::entity::Entity;
+    use crate::item;
+    use crate::monster;
+    use crate::shop::Shop;
+    use crate::status::{StatusEffect, StatusKind};
+    use crate::trap::{Feature, Hidden, Trap};
 
     #[test]
     fn test_chunk_generation() {
-        let mut map = MapManager::new();
+        let mut map = MapManager::new(0);
         map.generate_chunk_if_needed(0, 0);
 
         // Check if chunk exists
@@ -18,9 +26,26 @@ mod tests {
         assert!(floor_count > 0);
     }
 
+    #[test]
+    fn test_same_seed_same_world() {
+        let tiles = |map: &MapManager, key| map.chunks[&key].tiles.iter().map(|t| t.char).collect::<String>();
+        let (mut a, mut b) = (MapManager::new(42), MapManager::new(42));
+        // Both the first chunk and one carved to meet it come out the same
+        for key in [(0, 0), (1, 0), (2, 0)] {
+            a.generate_chunk_if_needed(key.0, key.1);
+            b.generate_chunk_if_needed(key.0, key.1);
+            assert_eq!(tiles(&a, key), tiles(&b, key));
+            assert_eq!(a.chunks[&key].hidden, b.chunks[&key].hidden);
+        }
+
+        let mut other = MapManager::new(43);
+        other.generate_chunk_if_needed(0, 0);
+        assert_ne!(tiles(&a, (0, 0)), tiles(&other, (0, 0)));
+    }
+
     #[test]
     fn test_connectivity() {
-        let mut map = MapManager::new();
+        let mut map = MapManager::new(0);
         // Generate (0,0) and (1,0)
         map.generate_chunk_if_needed(0, 0);
         map.generate_chunk_if_needed(1, 0);
@@ -44,7 +69,7 @@ mod tests {
 
     #[test]
     fn test_infinite_coordinates() {
-        let mut map = MapManager::new();
+        let mut map = MapManager::new(0);
         // Generate chunk at negative coordinates
         map.generate_chunk_if_needed(-5, -5);
 
@@ -56,4 +81,345 @@ mod tests {
         // Should not panic and return something valid (wall or floor)
         assert!(tile.char == '#' || tile.char == '.');
     }
+
+    /// Two solid-wall chunks side by side, (0,0) and (1,0), for hand-carved layouts.
+    fn walled_map() -> MapManager {
+        let mut map = MapManager::new(0);
+        map.chunks.insert((0, 0), Chunk::new(0, 0, 0));
+        map.chunks.insert((1, 0), Chunk::new(1, 0, 0));
+        map
+    }
+
+    #[test]
+    fn test_find_nearest_across_chunks() {
+        let mut map = walled_map();
+        let floor = Pos::new(CHUNK_SIZE + 2, 5);
+        map.set_tile(floor, Tile::floor());
+
+        let origin = Pos::new(CHUNK_SIZE - 2, 5);
+        let found = map.find_nearest(origin, 10, |_, t| t.is_walkable());
+        assert_eq!(found, Some(floor));
+
+        // Out of range
+        assert_eq!(map.find_nearest(origin, 3, |_, t| t.is_walkable()), None);
+    }
+
+    #[test]
+    fn test_flood_fill_region() {
+        let mut map = walled_map();
+        // A corridor crossing the chunk border, plus a separate room
+        for x in CHUNK_SIZE - 3..CHUNK_SIZE + 3 {
+            map.set_tile(Pos::new(x, 4), Tile::floor());
+        }
+        map.set_tile(Pos::new(2, 20), Tile::floor());
+
+        let region = map.flood_fill(Pos::new(CHUNK_SIZE - 3, 4), 100, |_, t| t.is_walkable());
+        assert_eq!(region.len(), 6);
+        assert!(region.contains(&Pos::new(CHUNK_SIZE + 2, 4)));
+        assert!(!region.contains(&Pos::new(2, 20)));
+
+        let limited = map.flood_fill(Pos::new(CHUNK_SIZE - 3, 4), 3, |_, t| t.is_walkable());
+        assert_eq!(limited.len(), 3);
+    }
+
+    #[test]
+    fn test_find_nearest_is_straight_line() {
+        let mut map = walled_map();
+        let origin = Pos::new(10, 10);
+        // The diagonal tile sits on a closer ring but is further away
+        let diagonal = Pos::new(13, 13);
+        let straight = Pos::new(10, 14);
+        map.set_tile(diagonal, Tile::floor());
+        map.set_tile(straight, Tile::floor());
+        assert_eq!(map.find_nearest(origin, 10, |_, t| t.is_walkable()), Some(straight));
+        assert_eq!(map.find_nearest(origin, 3, |_, t| t.is_walkable()), Some(diagonal));
+    }
+
+    #[test]
+    fn test_cast_ray_stops_at_wall() {
+        let mut map = walled_map();
+        for x in 1..10 {
+            map.set_tile(Pos::new(x, 1), Tile::floor());
+        }
+
+        let clear = map.cast_ray(Pos::new(1, 1), Pos::new(9, 1));
+        assert_eq!(clear.blocked_at, None);
+        assert_eq!(clear.path.last(), Some(&Pos::new(9, 1)));
+
+        let blocked = map.cast_ray(Pos::new(1, 1), Pos::new(15, 1));
+        assert_eq!(blocked.blocked_at, Some(Pos::new(10, 1)));
+        assert_eq!(blocked.path.len(), 9);
+    }
+
+    #[test]
+    fn test_ore_veins_face_open_floor() {
+        let mut map = MapManager::new(7);
+        for x in -2..3 {
+            map.generate_chunk_if_needed(x, 0);
+        }
+        let mut veins = 0;
+        for chunk in map.chunks.values() {
+            for y in 0..CHUNK_SIZE {
+                for x in 0..CHUNK_SIZE {
+                    if chunk.get_tile(x, y).is_some_and(|t| t.char != '*') {
+                        continue;
+                    }
+                    veins += 1;
+                    assert!(x > 0 && y > 0 && x < CHUNK_SIZE - 1 && y < CHUNK_SIZE - 1, "vein on the edge");
+                    let neighbours = [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)];
+                    assert!(neighbours.iter().any(|(nx, ny)| chunk.get_tile(*nx, *ny).is_some_and(|t| t.is_walkable())));
+                }
+            }
+        }
+        assert!(veins > 0);
+
+        // Mining leaves floor behind
+        let (item, left) = Tile::ore_vein().harvest().unwrap();
+        assert_eq!(item, "iron_ore");
+        assert!(left.is_walkable());
+    }
+
+    #[test]
+    fn test_noise_goes_round_walls_and_stops_at_doors() {
+        let mut map = walled_map();
+        // Two rooms side by side, joined by a doorway at (5, 1)
+        for y in 1..4 {
+            for x in (1..5).chain(6..10) {
+                map.set_tile(Pos::new(x, y), Tile::floor());
+            }
+        }
+        map.set_tile(Pos::new(5, 1), Tile::door());
+
+        let shut = map.noise_spread(Pos::new(4, 3), 20);
+        assert_eq!(shut.get(&Pos::new(1, 3)), Some(&3));
+        assert!(!shut.contains_key(&Pos::new(6, 3)));
+
+        // Once open the noise has to detour through the doorway
+        map.set_tile(Pos::new(5, 1), Tile::open_door());
+        let open = map.noise_spread(Pos::new(4, 3), 20);
+        assert_eq!(open.get(&Pos::new(6, 3)), Some(&6));
+
+        let quiet = map.noise_spread(Pos::new(4, 3), 3);
+        assert!(quiet.values().all(|steps| *steps <= 3));
+        assert!(!quiet.contains_key(&Pos::new(6, 1)));
+    }
+
+    #[test]
+    fn test_find_path_across_chunks() {
+        let mut map = walled_map();
+        // An L-shaped corridor crossing into the next chunk
+        for x in CHUNK_SIZE - 4..CHUNK_SIZE + 3 {
+            map.set_tile(Pos::new(x, 4), Tile::floor());
+        }
+        for y in 5..9 {
+            map.set_tile(Pos::new(CHUNK_SIZE + 2, y), Tile::floor());
+        }
+
+        let (from, to) = (Pos::new(CHUNK_SIZE - 4, 4), Pos::new(CHUNK_SIZE + 2, 8));
+        let path = map.find_path(from, to, 500, |_, t| t.is_walkable()).unwrap();
+        assert_eq!(path.last(), Some(&to));
+        // Five steps along, one cutting the corner, then three down
+        assert_eq!(path.len(), 9);
+        assert!(path.iter().all(|p| map.get_tile(*p).is_walkable()));
+
+        assert!(map.find_path(from, Pos::new(2, 20), 500, |_, t| t.is_walkable()).is_none());
+    }
+
+    #[test]
+    fn test_radius_query() {
+        let map = walled_map();
+        let tiles: Vec<_> = map.tiles_in_radius(Pos::new(5, 5), 1).collect();
+        // A radius of one covers the centre and its four orthogonal neighbours
+        assert_eq!(tiles.len(), 5);
+    }
+
+    #[test]
+    fn test_danger_grows_with_distance() {
+        assert_eq!(danger_level(0, 0), 1);
+        assert_eq!(danger_level(-1, 1), 2);
+        assert_eq!(danger_level(3, -1), 4);
+        assert_eq!(Chunk::new(0, -5, 0).danger_level(), 6);
+    }
+
+    #[test]
+    fn test_unloaded_chunk_keeps_tiles_and_entities() {
+        let mut map = MapManager::new(7);
+        assert!(map.generate_chunk_if_needed(2, 0).generated);
+        let tiles: Vec<char> = map.chunks[&(2, 0)].tiles.iter().map(|t| t.char).collect();
+
+        let origin = map.chunks[&(2, 0)].bounds().top_left();
+        let mut goblin = monster::create("goblin", origin + Pos::new(3, 4), 1).unwrap();
+        goblin.fighter.as_mut().unwrap().hp = 2;
+        goblin.effects.add(StatusEffect::new(StatusKind::Poisoned, 3, 1).by_player());
+        goblin.faction = Some("wildlife".to_string());
+        let mut rat = monster::create("rat", origin, 1).unwrap();
+        rat.make_corpse();
+        let dagger = item::on_floor(item::make("dagger").unwrap(), origin + Pos::new(1, 1));
+        let hermit = Entity::new(origin + Pos::new(2, 2), 'h', BEIGE, "old hermit").blocking().with_dialogue("hermit.start");
+
+        map.unload_chunk(2, 0, &[goblin, rat, dagger, hermit]);
+        assert!(!map.chunks.contains_key(&(2, 0)));
+
+        // Coming back restores what was there instead of generating afresh
+        let load = map.generate_chunk_if_needed(2, 0);
+        assert!(!load.generated);
+        let restored_tiles: Vec<char> = map.chunks[&(2, 0)].tiles.iter().map(|t| t.char).collect();
+        assert_eq!(restored_tiles, tiles);
+
+        let names: Vec<&str> = load.restored.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["goblin", "rat corpse", "dagger", "old hermit"]);
+        let goblin = &load.restored[0];
+        assert_eq!(goblin.pos, origin + Pos::new(3, 4));
+        assert_eq!(goblin.fighter.map(|f| f.hp), Some(2));
+        assert!(goblin.effects.harmed_by_player());
+        assert_eq!(goblin.faction.as_deref(), Some("wildlife"));
+        assert!(load.restored[1].fighter.is_none());
+        assert_eq!(load.restored[3].dialogue.as_deref(), Some("hermit.start"));
+        assert_eq!(load.restored[3].color, BEIGE);
+    }
+
+    #[test]
+    fn test_hidden_features_stay_hidden_until_found() {
+        let mut map = walled_map();
+        for x in 1..6 {
+            map.set_tile(Pos::new(x, 1), Tile::floor());
+            map.set_tile(Pos::new(x, 3), Tile::floor());
+        }
+        let chunk = map.chunks.get_mut(&(0, 0)).unwrap();
+        chunk.hidden.insert(Pos::new(3, 2), Hidden::new(Feature::SecretDoor));
+        chunk.hidden.insert(Pos::new(4, 1), Hidden::new(Feature::Trap(Trap::Pit)));
+        chunk.hidden.insert(Pos::new(2, 3), Hidden::new(Feature::Trap(Trap::Alarm)));
+
+        // A secret door is a wall until it's found, then just a door
+        assert!(!map.get_tile(Pos::new(3, 2)).is_walkable());
+        assert_eq!(map.reveal(Pos::new(3, 2)), Some(Feature::SecretDoor));
+        assert!(map.get_tile(Pos::new(3, 2)).is_closed_door());
+        assert_eq!(map.hidden_at(Pos::new(3, 2)), None);
+
+        // A found trap is remembered, through unloading and all
+        assert_eq!(map.reveal(Pos::new(4, 1)), Some(Feature::Trap(Trap::Pit)));
+        assert_eq!(map.reveal(Pos::new(4, 2)), None);
+        map.unload_chunk(0, 0, &[]);
+        map.generate_chunk_if_needed(0, 0);
+        assert_eq!(map.hidden_at(Pos::new(4, 1)).and_then(|h| h.known_trap()), Some(Trap::Pit));
+        assert_eq!(map.hidden_at(Pos::new(2, 3)), Some(Hidden::new(Feature::Trap(Trap::Alarm))));
+        assert!(map.get_tile(Pos::new(3, 2)).is_closed_door());
+    }
+
+    #[test]
+    fn test_secret_doors_join_two_passages() {
+        let mut map = MapManager::new(3);
+        for x in -2..3 {
+            map.generate_chunk_if_needed(x, 1);
+        }
+        for chunk in map.chunks.values() {
+            for (pos, hidden) in &chunk.hidden {
+                let floor = |dx: i32, dy: i32| chunk.get_tile(pos.x + dx, pos.y + dy).is_some_and(|t| t.is_walkable());
+                match hidden.feature {
+                    Feature::SecretDoor => {
+                        assert_eq!(chunk.get_tile(pos.x, pos.y).map(|t| t.char), Some('#'));
+                        assert!((floor(0, -1) && floor(0, 1)) || (floor(-1, 0) && floor(1, 0)));
+                    }
+                    Feature::Trap(_) => {
+                        assert_eq!(chunk.get_tile(pos.x, pos.y).map(|t| t.char), Some('.'));
+                        assert!(chunk.danger_level() > 1);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_unloaded_chunk_keeps_monster_levels() {
+        let mut map = MapManager::new(7);
+        map.generate_chunk_if_needed(0, 0);
+        let orc = monster::create("orc", Pos::new(3, 3), 5).unwrap();
+        let fighter = orc.fighter.unwrap();
+        map.unload_chunk(0, 0, &[orc]);
+
+        let load = map.generate_chunk_if_needed(0, 0);
+        let orc = &load.restored[0];
+        assert_eq!(orc.level, 5);
+        assert_eq!(orc.fighter.map(|f| (f.max_hp, f.attack, f.defense)), Some((fighter.max_hp, fighter.attack, fighter.defense)));
+    }
+
+    #[test]
+    fn test_unloaded_chunk_keeps_merchant_stock_and_gold() {
+        let mut map = MapManager::new(7);
+        map.generate_chunk_if_needed(-1, 0);
+        let origin = map.chunks[&(-1, 0)].bounds().top_left();
+        let stock = vec![item::make("sword").unwrap(), item::make("bandage").unwrap()];
+        let pedlar = Entity::new(origin, '@', GOLD, "pedlar")
+            .blocking()
+            .with_dialogue("pedlar.start")
+            .with_shop(Shop::new("merchant_stock", stock, 120));
+        let coins = item::on_floor(item::gold(17), origin + Pos::new(1, 0));
+
+        map.unload_chunk(-1, 0, &[pedlar, coins]);
+        let load = map.generate_chunk_if_needed(-1, 0);
+
+        let shop = load.restored[0].shop.as_ref().unwrap();
+        assert_eq!(shop.table, "merchant_stock");
+        assert_eq!(shop.restocked_at, 120);
+        let ids: Vec<&str> = shop.stock.iter().map(|i| i.id).collect();
+        assert_eq!(ids, vec!["sword", "bandage"]);
+        assert_eq!(load.restored[1].item.as_ref().map(|i| i.value), Some(17));
+    }
+
+    #[test]
+    fn test_new_chunk_restores_unloaded_neighbours() {
+        let mut map = MapManager::new(0);
+        map.generate_chunk_if_needed(0, 0);
+        map.unload_chunk(0, 0, &[]);
+
+        // (1, 0) has to match (0, 0)'s east edge, so (0, 0) comes back first
+        assert!(map.generate_chunk_if_needed(1, 0).generated);
+        assert!(map.chunks.contains_key(&(0, 0)));
+        assert!(map.saved.is_empty());
+    }
+
+    #[test]
+    fn test_reloaded_chunk_rolls_on() {
+        let roll = |map: &MapManager| (0..8).map(|_| map.chunks[&(1, 1)].rng.gen_range(0, 1000)).collect::<Vec<i32>>();
+        let played = || {
+            let mut map = MapManager::new(9);
+            map.generate_chunk_if_needed(1, 1);
+            let rolls = roll(&map);
+            map.unload_chunk(1, 1, &[]);
+            (map, rolls)
+        };
+        let (mut map, before) = played();
+        let (twin, _) = played();
+        // The same seed and the same rolls save the same way...
+        assert_eq!(map.saved, twin.saved);
+
+        // ...but coming back doesn't replay them
+        map.generate_chunk_if_needed(1, 1);
+        assert_ne!(roll(&map), before);
+    }
+
+    #[test]
+    fn test_unsaveable_entity_is_left_out_with_a_warning() {
+        let mut map = walled_map();
+        let rat = monster::create("rat", Pos::new(1, 1), 1).unwrap();
+        let ghost = Entity::new(Pos::new(2, 2), 'G', GOLD, "ghost");
+        let warnings = map.unload_chunk(0, 0, &[rat, ghost]);
+        assert_eq!(warnings, vec!["The ghost can't be saved and is lost."]);
+
+        let load = map.generate_chunk_if_needed(0, 0);
+        assert!(!load.generated);
+        assert_eq!(load.restored.len(), 1);
+        assert_eq!(load.restored[0].name, "rat");
+    }
+
+    #[test]
+    fn test_broken_save_is_generated_afresh() {
+        let mut map = MapManager::new(0);
+        map.saved.insert((3, 3), "[chunk 3,3]\nrow = not a chunk\n".to_string());
+        let load = map.generate_chunk_if_needed(3, 3);
+        assert!(load.generated);
+        assert_eq!(load.warnings.len(), 1);
+        assert!(map.chunks.contains_key(&(3, 3)));
+        assert!(map.saved.is_empty());
+    }
 }
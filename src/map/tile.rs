@@ -17,27 +17,63 @@ impl Tile {
         }
     }
 
-    pub fn empty() -> Self {
-        Self {
-            char: ' ',
-            color: BLACK,
-            bg_color: None,
-        }
+    pub fn wall() -> Self {
+        Self::new('#', DARKGRAY, None)
     }
 
-    pub fn wall() -> Self {
-        Self {
-            char: '#',
-            color: DARKGRAY,
-            bg_color: None,
+    pub fn floor() -> Self {
+        Self::new('.', LIGHTGRAY, None)
+    }
+
+    pub fn door() -> Self {
+        Self::new('+', BROWN, None)
+    }
+
+    pub fn open_door() -> Self {
+        Self::new('\'', BROWN, None)
+    }
+
+    /// Rock with iron in it. Mining it leaves open floor.
+    pub fn ore_vein() -> Self {
+        Self::new('*', ORANGE, None)
+    }
+
+    /// Floor overgrown with herbs.
+    pub fn herbs() -> Self {
+        Self::new('"', GREEN, None)
+    }
+
+    /// The standard tile drawn with `c`, for restoring saved chunks.
+    pub fn from_char(c: char) -> Option<Self> {
+        match c {
+            '#' => Some(Self::wall()),
+            '.' => Some(Self::floor()),
+            '+' => Some(Self::door()),
+            '\'' => Some(Self::open_door()),
+            '*' => Some(Self::ore_vein()),
+            '"' => Some(Self::herbs()),
+            _ => None,
         }
     }
 
-    pub fn floor() -> Self {
-        Self {
-            char: '.',
-            color: LIGHTGRAY,
-            bg_color: None,
+    /// Whether actors can stand on this tile. Anything that can't be stood
+    /// on also blocks sight.
+    pub fn is_walkable(&self) -> bool {
+        !matches!(self.char, '#' | '+' | '*')
+    }
+
+    /// A closed door, which opens when bumped into.
+    pub fn is_closed_door(&self) -> bool {
+        self.char == '+'
+    }
+
+    /// What gathering from this tile yields: the item's id and the tile
+    /// left behind.
+    pub fn harvest(&self) -> Option<(&'static str, Tile)> {
+        match self.char {
+            '*' => Some(("iron_ore", Self::floor())),
+            '"' => Some(("herb", Self::floor())),
+            _ => None,
         }
     }
-}
\ No newline at end of file
+}
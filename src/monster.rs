@@ -0,0 +1,246 @@
+// monster.rs
+//
+// Monster templates, looked up by id when spawning.
+use macroquad::prelude::*;
+use crate::ability::Spellbook;
+use crate::ai::Ai;
+use crate::combat::Fighter;
+use crate::entity::Entity;
+use crate::geometry::Pos;
+use crate::status::{StatusEffect, StatusKind};
+
+pub struct MonsterTemplate {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub glyph: char,
+    pub color: Color,
+    pub max_hp: i32,
+    pub attack: i32,
+    pub defense: i32,
+    pub hit_chance: i32,
+    /// Energy gained per tick; `NORMAL_SPEED` is the player's.
+    pub speed: i32,
+    pub sight_radius: i32,
+    pub hearing_radius: i32,
+    pub flee_threshold: f32,
+    /// Status effect inflicted by a successful hit.
+    pub on_hit: Option<StatusEffect>,
+    /// Experience for killing one at danger level 1.
+    pub xp: i32,
+    /// Ids of abilities from `data/abilities.txt`.
+    pub abilities: &'static [&'static str],
+    /// Id of its faction in `data/factions.txt`.
+    pub faction: &'static str,
+}
+
+/// Mana every spellcasting monster starts with.
+const CASTER_MANA: i32 = 10;
+
+pub const MONSTERS: &[MonsterTemplate] = &[
+    MonsterTemplate {
+        id: "rat",
+        name: "rat",
+        glyph: 'r',
+        color: BROWN,
+        max_hp: 4,
+        attack: 2,
+        defense: 0,
+        hit_chance: 60,
+        speed: 12,
+        sight_radius: 6,
+        hearing_radius: 4,
+        flee_threshold: 0.5,
+        on_hit: None,
+        xp: 2,
+        abilities: &[],
+        faction: "beasts",
+    },
+    MonsterTemplate {
+        id: "goblin",
+        name: "goblin",
+        glyph: 'g',
+        color: LIME,
+        max_hp: 8,
+        attack: 4,
+        defense: 1,
+        hit_chance: 65,
+        speed: 10,
+        sight_radius: 8,
+        hearing_radius: 3,
+        flee_threshold: 0.3,
+        on_hit: None,
+        xp: 5,
+        abilities: &[],
+        faction: "goblins",
+    },
+    MonsterTemplate {
+        id: "orc",
+        name: "orc",
+        glyph: 'o',
+        color: ORANGE,
+        max_hp: 14,
+        attack: 6,
+        defense: 2,
+        hit_chance: 70,
+        speed: 8,
+        sight_radius: 7,
+        hearing_radius: 2,
+        flee_threshold: 0.0,
+        on_hit: None,
+        xp: 10,
+        abilities: &[],
+        faction: "orcs",
+    },
+    MonsterTemplate {
+        id: "giant_spider",
+        name: "giant spider",
+        glyph: 's',
+        color: PURPLE,
+        max_hp: 6,
+        attack: 2,
+        defense: 0,
+        hit_chance: 70,
+        speed: 11,
+        sight_radius: 6,
+        hearing_radius: 5,
+        flee_threshold: 0.0,
+        on_hit: Some(StatusEffect::new(StatusKind::Poisoned, 4, 1)),
+        xp: 6,
+        abilities: &["poison_spit"],
+        faction: "beasts",
+    },
+    MonsterTemplate {
+        id: "goblin_shaman",
+        name: "goblin shaman",
+        glyph: 'g',
+        color: VIOLET,
+        max_hp: 7,
+        attack: 2,
+        defense: 0,
+        hit_chance: 60,
+        speed: 10,
+        sight_radius: 8,
+        hearing_radius: 3,
+        flee_threshold: 0.4,
+        on_hit: None,
+        xp: 9,
+        abilities: &["hex", "spark", "mend"],
+        faction: "goblins",
+    },
+    MonsterTemplate {
+        id: "wild_boar",
+        name: "wild boar",
+        glyph: 'q',
+        color: DARKBROWN,
+        max_hp: 10,
+        attack: 4,
+        defense: 1,
+        hit_chance: 65,
+        speed: 11,
+        sight_radius: 6,
+        hearing_radius: 4,
+        flee_threshold: 0.2,
+        on_hit: None,
+        xp: 6,
+        abilities: &[],
+        faction: "wildlife",
+    },
+    // Companions: these never turn up in the spawn tables
+    MonsterTemplate {
+        id: "dog",
+        name: "dog",
+        glyph: 'd',
+        color: BEIGE,
+        max_hp: 12,
+        attack: 3,
+        defense: 0,
+        hit_chance: 70,
+        speed: 12,
+        sight_radius: 8,
+        hearing_radius: 5,
+        flee_threshold: 0.25,
+        on_hit: None,
+        xp: 4,
+        abilities: &[],
+        faction: "wildlife",
+    },
+    MonsterTemplate {
+        id: "sellsword",
+        name: "sellsword",
+        glyph: '@',
+        color: SKYBLUE,
+        max_hp: 22,
+        attack: 5,
+        defense: 2,
+        hit_chance: 75,
+        speed: 10,
+        sight_radius: 8,
+        hearing_radius: 3,
+        flee_threshold: 0.2,
+        on_hit: None,
+        xp: 12,
+        abilities: &[],
+        faction: "sellswords",
+    },
+];
+
+pub fn template(id: &str) -> Option<&'static MonsterTemplate> {
+    MONSTERS.iter().find(|m| m.id == id)
+}
+
+/// Builds a monster entity from its template, ready to spawn. Stats grow
+/// with the danger level of where it spawns; level 1 is the template as is.
+pub fn create(id: &str, pos: Pos, level: i32) -> Option<Entity> {
+    let t = template(id)?;
+    let bonus = (level - 1).max(0);
+    let mut fighter = Fighter::new(
+        t.max_hp + t.max_hp * bonus / 4,
+        t.attack + bonus / 2,
+        t.defense + bonus / 3,
+        t.hit_chance,
+    );
+    if let Some(effect) = t.on_hit {
+        fighter = fighter.with_on_hit(effect);
+    }
+    let mut entity = Entity::new(pos, t.glyph, t.color, t.name)
+        .with_template(t.id)
+        .blocking()
+        .with_fighter(fighter)
+        .with_speed(t.speed)
+        .with_ai(Ai::new(t.sight_radius, t.hearing_radius, t.flee_threshold))
+        .with_faction(t.faction);
+    entity.level = level.max(1);
+    if !t.abilities.is_empty() {
+        let known = t.abilities.iter().map(|a| a.to_string()).collect();
+        entity = entity.with_spellbook(Spellbook::new(known, CASTER_MANA));
+    }
+    Some(entity)
+}
+
+/// Experience for killing a monster created at `level`. Tougher monsters
+/// are worth more.
+pub fn xp_value(id: &str, level: i32) -> i32 {
+    let Some(t) = template(id) else { return 0 };
+    t.xp + t.xp * (level - 1).max(0) / 2
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats_scale_with_level() {
+        let stats = |level| create("orc", Pos::new(0, 0), level).unwrap().fighter.unwrap();
+        let (base, deep) = (stats(1), stats(5));
+        assert_eq!((base.max_hp, base.attack, base.defense), (14, 6, 2));
+        assert_eq!((deep.max_hp, deep.attack, deep.defense), (28, 8, 3));
+        assert_eq!((xp_value("orc", 1), xp_value("orc", 5)), (10, 30));
+    }
+
+    #[test]
+    fn test_hearing_within_noise_bounds() {
+        for t in MONSTERS {
+            assert!(t.hearing_radius <= crate::noise::MAX_HEARING, "{} hears too far", t.id);
+        }
+    }
+}
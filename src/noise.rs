@@ -0,0 +1,57 @@
+// noise.rs
+//
+// How loud things are. Noise spreads from where it was made through open
+// ground, one tile of reach lost per step, so walls and closed doors muffle
+// it (see `MapManager::noise_spread`). Monsters that hear a noise go to
+// investigate.
+
+/// Hearing of an ordinary monster. Keener ears hear noise from further off.
+pub const NORMAL_HEARING: i32 = 3;
+/// Keenest hearing any monster has, which bounds how far from a noise
+/// anything can hear it.
+pub const MAX_HEARING: i32 = 6;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Noise {
+    /// Walking about; quieter for a stealthy player.
+    Footsteps,
+    /// Any blow struck, hit or miss.
+    Combat,
+    /// A door being opened.
+    Door,
+    /// Rock being turned to floor.
+    Digging,
+    /// Something thrown landing.
+    Impact,
+}
+
+impl Noise {
+    /// Steps the noise carries to a monster with normal hearing.
+    pub fn loudness(self) -> i32 {
+        match self {
+            Noise::Footsteps => 7,
+            Noise::Combat => 10,
+            Noise::Door => 8,
+            Noise::Digging => 12,
+            Noise::Impact => 6,
+        }
+    }
+}
+
+/// Steps a noise of `loudness` carries to a listener with `hearing`.
+pub fn reach(loudness: i32, hearing: i32) -> i32 {
+    (loudness + hearing - NORMAL_HEARING).max(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keen_ears_hear_further() {
+        let steps = Noise::Footsteps.loudness();
+        assert_eq!(reach(steps, NORMAL_HEARING), steps);
+        assert!(reach(steps, 5) > reach(steps, 2));
+        assert_eq!(reach(1, 0), 0);
+    }
+}
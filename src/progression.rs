@@ -0,0 +1,185 @@
+// progression.rs
+//
+// Experience, levels and the attributes the player raises as they level up.
+// The player's combat stats and sight are derived from their attributes and
+// level rather than stored on their own.
+
+/// Each level takes this much more XP to reach than the one before.
+pub const XP_STEP: i32 = 20;
+/// Attribute points handed out per level gained.
+pub const POINTS_PER_LEVEL: i32 = 2;
+/// What every attribute starts at.
+pub const BASE_ATTRIBUTE: i32 = 10;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Attribute {
+    Strength,
+    Toughness,
+    Agility,
+    Perception,
+}
+
+impl Attribute {
+    pub const ALL: [Attribute; 4] = [
+        Attribute::Strength,
+        Attribute::Toughness,
+        Attribute::Agility,
+        Attribute::Perception,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Attribute::Strength => "Strength",
+            Attribute::Toughness => "Toughness",
+            Attribute::Agility => "Agility",
+            Attribute::Perception => "Perception",
+        }
+    }
+
+    /// What raising it improves, for the character sheet.
+    pub fn effect(self) -> &'static str {
+        match self {
+            Attribute::Strength => "+1 attack",
+            Attribute::Toughness => "+3 max HP",
+            Attribute::Agility => "+2% to hit, +1 defense and stealth per 2",
+            Attribute::Perception => "+2 max mana, +1 sight and search per 2",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Attributes {
+    pub strength: i32,
+    pub toughness: i32,
+    pub agility: i32,
+    pub perception: i32,
+}
+
+impl Attributes {
+    pub fn new() -> Self {
+        Self {
+            strength: BASE_ATTRIBUTE,
+            toughness: BASE_ATTRIBUTE,
+            agility: BASE_ATTRIBUTE,
+            perception: BASE_ATTRIBUTE,
+        }
+    }
+
+    pub fn get(&self, attribute: Attribute) -> i32 {
+        match attribute {
+            Attribute::Strength => self.strength,
+            Attribute::Toughness => self.toughness,
+            Attribute::Agility => self.agility,
+            Attribute::Perception => self.perception,
+        }
+    }
+
+    pub fn raise(&mut self, attribute: Attribute) {
+        match attribute {
+            Attribute::Strength => self.strength += 1,
+            Attribute::Toughness => self.toughness += 1,
+            Attribute::Agility => self.agility += 1,
+            Attribute::Perception => self.perception += 1,
+        }
+    }
+
+    /// Stats that follow from the attributes at `level`, before equipment.
+    pub fn derive(&self, level: i32) -> Derived {
+        Derived {
+            max_hp: 30 + 3 * (self.toughness - BASE_ATTRIBUTE) + 4 * (level - 1),
+            attack: 5 + self.strength - BASE_ATTRIBUTE,
+            defense: 1 + (self.agility - BASE_ATTRIBUTE) / 2,
+            hit_chance: (80 + 2 * (self.agility - BASE_ATTRIBUTE)).min(95),
+            sight_bonus: (self.perception - BASE_ATTRIBUTE) / 2,
+            max_mana: 10 + 2 * (self.perception - BASE_ATTRIBUTE) + 2 * (level - 1),
+            stealth: 2 + (self.agility - BASE_ATTRIBUTE) / 2,
+            search: 3 + (self.perception - BASE_ATTRIBUTE) / 2,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Derived {
+    pub max_hp: i32,
+    pub attack: i32,
+    pub defense: i32,
+    pub hit_chance: i32,
+    /// Added to the player's field of view.
+    pub sight_bonus: i32,
+    pub max_mana: i32,
+    /// Taken off how far the player's footsteps carry.
+    pub stealth: i32,
+    /// How good the player is at finding traps and secret doors.
+    pub search: i32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Experience {
+    pub level: i32,
+    /// Total XP earned so far.
+    pub xp: i32,
+    /// Attribute points waiting to be spent.
+    pub unspent: i32,
+}
+
+impl Experience {
+    pub fn new() -> Self {
+        Self { level: 1, xp: 0, unspent: 0 }
+    }
+
+    /// Total XP needed to reach `level`: 20, 60, 120, ... for levels 2, 3, 4.
+    pub fn threshold(level: i32) -> i32 {
+        XP_STEP * level * (level - 1) / 2
+    }
+
+    pub fn next_level_xp(&self) -> i32 {
+        Self::threshold(self.level + 1)
+    }
+
+    /// Adds XP and returns how many levels it was worth.
+    pub fn gain(&mut self, amount: i32) -> i32 {
+        self.xp += amount;
+        let mut gained = 0;
+        while self.xp >= self.next_level_xp() {
+            self.level += 1;
+            self.unspent += POINTS_PER_LEVEL;
+            gained += 1;
+        }
+        gained
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_level_thresholds() {
+        let mut exp = Experience::new();
+        assert_eq!(exp.gain(19), 0);
+        assert_eq!(exp.gain(1), 1);
+        assert_eq!((exp.level, exp.unspent), (2, POINTS_PER_LEVEL));
+        // One big reward can be worth several levels
+        assert_eq!(exp.gain(100), 2);
+        assert_eq!(exp.level, 4);
+        assert_eq!(exp.next_level_xp(), 200);
+    }
+
+    #[test]
+    fn test_derived_stats() {
+        let mut attrs = Attributes::new();
+        let start = attrs.derive(1);
+        assert_eq!((start.max_hp, start.attack, start.defense, start.hit_chance), (30, 5, 1, 80));
+
+        attrs.raise(Attribute::Agility);
+        attrs.raise(Attribute::Agility);
+        attrs.raise(Attribute::Toughness);
+        let raised = attrs.derive(2);
+        assert_eq!((raised.max_hp, raised.defense, raised.hit_chance), (37, 2, 84));
+        assert_eq!(raised.stealth, start.stealth + 1);
+
+        attrs.raise(Attribute::Perception);
+        attrs.raise(Attribute::Perception);
+        assert_eq!(attrs.derive(2).search, start.search + 1);
+    }
+}
@@ -0,0 +1,316 @@
+// quest.rs
+//
+// Quests, loaded from `data/quests.txt`, and the player's quest log. A quest
+// has one goal; the game reports what the player does as `Event`s, and the
+// log counts them towards every active quest they help with.
+use crate::data::{self, DataError, Field, Section};
+use crate::item;
+use crate::monster;
+
+const EMBEDDED: &str = include_str!("../data/quests.txt");
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Goal {
+    /// Kill this many of a monster.
+    Kill { monster: &'static str, count: i32 },
+    /// Carry this many of an item.
+    Fetch { item: &'static str, count: i32 },
+    /// Set foot in this chunk.
+    Reach { chunk: (i32, i32) },
+    /// Set foot in this many chunks never visited before.
+    Explore { count: i32 },
+}
+
+impl Goal {
+    /// What the progress counter has to reach.
+    pub fn target(&self) -> i32 {
+        match self {
+            Goal::Kill { count, .. } | Goal::Fetch { count, .. } | Goal::Explore { count } => *count,
+            Goal::Reach { .. } => 1,
+        }
+    }
+
+    pub fn describe(&self, progress: i32) -> String {
+        match self {
+            Goal::Kill { monster, count } => {
+                let name = monster::template(monster).map_or(*monster, |t| t.name);
+                format!("Kill {} {}s ({}/{})", count, name, progress, count)
+            }
+            Goal::Fetch { item, count } => {
+                let name = item::template(item).map_or(*item, |t| t.name);
+                format!("Carry {} {}s ({}/{})", count, name, progress, count)
+            }
+            Goal::Reach { chunk: (x, y) } => format!("Reach chunk {},{}", x, y),
+            Goal::Explore { count } => format!("Explore {} new chunks ({}/{})", count, progress, count),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Reward {
+    Gold(i32),
+    Xp(i32),
+    Item(&'static str),
+    /// Standing with a faction.
+    Reputation(String, i32),
+}
+
+#[derive(Clone, Debug)]
+pub struct Quest {
+    pub id: String,
+    pub name: String,
+    pub text: String,
+    pub goal: Goal,
+    pub rewards: Vec<Reward>,
+}
+
+pub struct Quests {
+    list: Vec<Quest>,
+}
+
+impl Quests {
+    /// Loads `data/quests.txt`, falling back to the built-in copy. Also
+    /// returns a warning if the file on disk had to be ignored.
+    pub fn load() -> (Self, Option<String>) {
+        data::load("quests.txt", EMBEDDED, Self::from_sections)
+    }
+
+    pub fn from_sections(sections: &[Section]) -> Result<Self, DataError> {
+        let mut list: Vec<Quest> = Vec::new();
+        for section in sections {
+            if section.kind != "quest" {
+                return Err(section.error(format!("unknown section kind '{}'", section.kind)));
+            }
+            if list.iter().any(|q| q.id == section.name) {
+                return Err(section.error(format!("quest '{}' is defined twice", section.name)));
+            }
+            list.push(parse_quest(section)?);
+        }
+        Ok(Self { list })
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Quest> {
+        self.list.iter().find(|q| q.id == id)
+    }
+}
+
+/// Something the player did that a quest might be waiting for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event<'a> {
+    /// Killed a monster made from this template.
+    Killed(&'a str),
+    /// Now carries this many of an item.
+    Carrying(&'a str, i32),
+    /// Stepped into this chunk.
+    Entered(i32, i32),
+    /// Stepped into a chunk for the first time.
+    Explored,
+}
+
+/// How far the player has got with a quest.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stage {
+    /// Not taken on yet.
+    New,
+    Active,
+    Done,
+}
+
+impl Stage {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "new" => Some(Stage::New),
+            "active" => Some(Stage::Active),
+            "done" => Some(Stage::Done),
+            _ => None,
+        }
+    }
+}
+
+/// A quest the player is working on.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Progress {
+    pub quest: String,
+    pub count: i32,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct QuestLog {
+    /// In the order they were taken on.
+    pub active: Vec<Progress>,
+    /// Ids of quests already completed.
+    pub finished: Vec<String>,
+}
+
+impl QuestLog {
+    pub fn stage(&self, id: &str) -> Stage {
+        if self.active.iter().any(|p| p.quest == id) {
+            Stage::Active
+        } else if self.finished.iter().any(|f| f == id) {
+            Stage::Done
+        } else {
+            Stage::New
+        }
+    }
+
+    /// Takes on a quest. Returns false if it already has been.
+    pub fn start(&mut self, id: &str) -> bool {
+        if self.stage(id) != Stage::New {
+            return false;
+        }
+        self.active.push(Progress { quest: id.to_string(), count: 0 });
+        true
+    }
+
+    /// Counts `event` towards every active quest. Returns the quests it
+    /// completed, which move from active to finished.
+    pub fn on_event(&mut self, quests: &Quests, event: Event) -> Vec<String> {
+        let mut done = Vec::new();
+        for progress in &mut self.active {
+            let Some(quest) = quests.get(&progress.quest) else { continue };
+            match (&quest.goal, event) {
+                (Goal::Kill { monster, .. }, Event::Killed(killed)) if *monster == killed => progress.count += 1,
+                (Goal::Fetch { item, .. }, Event::Carrying(carried, n)) if *item == carried => progress.count = n,
+                (Goal::Reach { chunk }, Event::Entered(x, y)) if *chunk == (x, y) => progress.count = 1,
+                (Goal::Explore { .. }, Event::Explored) => progress.count += 1,
+                _ => continue,
+            }
+            if progress.count >= quest.goal.target() {
+                done.push(progress.quest.clone());
+            }
+        }
+        self.active.retain(|p| !done.contains(&p.quest));
+        self.finished.extend(done.iter().cloned());
+        done
+    }
+}
+
+fn parse_quest(section: &Section) -> Result<Quest, DataError> {
+    let field = |key: &str| section.get(key).ok_or_else(|| section.error(format!("quest needs a {}", key)));
+
+    let mut rewards = Vec::new();
+    for f in section.fields.iter().filter(|f| f.key == "reward") {
+        rewards.push(parse_reward(f)?);
+    }
+    if let Some(f) = section.fields.iter().find(|f| !["name", "text", "goal", "reward"].contains(&f.key.as_str())) {
+        return Err(f.error(format!("unknown key '{}'", f.key)));
+    }
+
+    Ok(Quest {
+        id: section.name.clone(),
+        name: field("name")?.value.clone(),
+        text: field("text")?.value.clone(),
+        goal: parse_goal(field("goal")?)?,
+        rewards,
+    })
+}
+
+fn number(field: &Field, word: &str) -> Result<i32, DataError> {
+    word.parse().map_err(|_| field.error(format!("'{}' is not a number", word)))
+}
+
+fn monster_id(field: &Field, id: &str) -> Result<&'static str, DataError> {
+    monster::template(id).map(|t| t.id).ok_or_else(|| field.error(format!("no monster called '{}'", id)))
+}
+
+fn item_id(field: &Field, id: &str) -> Result<&'static str, DataError> {
+    item::template(id).map(|t| t.id).ok_or_else(|| field.error(format!("no item called '{}'", id)))
+}
+
+fn parse_goal(field: &Field) -> Result<Goal, DataError> {
+    let words: Vec<&str> = field.value.split_whitespace().collect();
+    Ok(match words.as_slice() {
+        ["kill", n, id] => Goal::Kill { monster: monster_id(field, id)?, count: number(field, n)? },
+        ["fetch", n, id] => Goal::Fetch { item: item_id(field, id)?, count: number(field, n)? },
+        ["reach", x, y] => Goal::Reach { chunk: (number(field, x)?, number(field, y)?) },
+        ["explore", n] => Goal::Explore { count: number(field, n)? },
+        _ => return Err(field.error(format!("unknown goal '{}'", field.value))),
+    })
+}
+
+fn parse_reward(field: &Field) -> Result<Reward, DataError> {
+    let words: Vec<&str> = field.value.split_whitespace().collect();
+    Ok(match words.as_slice() {
+        ["gold", n] => Reward::Gold(number(field, n)?),
+        ["xp", n] => Reward::Xp(number(field, n)?),
+        ["item", id] => Reward::Item(item_id(field, id)?),
+        ["reputation", faction, n] => Reward::Reputation(faction.to_string(), number(field, n)?),
+        _ => return Err(field.error(format!("unknown reward '{}'", field.value))),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialogue::{Dialogues, Effect};
+    use crate::faction::Factions;
+    use crate::item::ItemKind;
+
+    fn quests(text: &str) -> Result<Quests, DataError> {
+        Quests::from_sections(&data::parse(text).unwrap())
+    }
+
+    #[test]
+    fn test_builtin_quests_are_valid() {
+        let all = quests(EMBEDDED).unwrap();
+        let factions = Factions::from_sections(&data::parse(include_str!("../data/factions.txt")).unwrap()).unwrap();
+        for quest in &all.list {
+            for reward in &quest.rewards {
+                if let Reward::Reputation(faction, _) = reward {
+                    assert!(factions.get(faction).is_some(), "{} rewards missing faction {}", quest.id, faction);
+                }
+            }
+        }
+
+        // Everything that hands out a quest names one that exists
+        let dialogues = Dialogues::from_sections(&data::parse(include_str!("../data/dialogue.txt")).unwrap()).unwrap();
+        let effects = dialogues.nodes.values().flat_map(|n| n.effects.iter().chain(n.choices.iter().flat_map(|c| &c.effects)));
+        for effect in effects {
+            if let Effect::Quest(id) = effect {
+                assert!(all.get(id).is_some(), "dialogue gives missing quest {}", id);
+            }
+        }
+        for template in item::ITEMS {
+            if let ItemKind::Note(id) = template.kind {
+                assert!(all.get(id).is_some(), "{} starts missing quest {}", template.id, id);
+            }
+        }
+    }
+
+    #[test]
+    fn test_rejects_bad_quests() {
+        assert!(quests("[quest a]\nname = A\ntext = A\ngoal = kill 3 dragon\n").is_err());
+        assert!(quests("[quest a]\nname = A\ntext = A\ngoal = dance\n").is_err());
+        assert!(quests("[quest a]\nname = A\ntext = A\ngoal = explore 2\nreward = hugs\n").is_err());
+        assert!(quests("[quest a]\nname = A\ngoal = explore 2\n").is_err());
+    }
+
+    #[test]
+    fn test_progress() {
+        let all = quests(
+            "[quest rats]\nname = R\ntext = R\ngoal = kill 2 rat\n\
+             [quest far]\nname = F\ntext = F\ngoal = reach 3 -1\n\
+             [quest shrooms]\nname = S\ntext = S\ngoal = fetch 2 strange_mushroom\n",
+        )
+        .unwrap();
+        let mut log = QuestLog::default();
+        assert!(log.start("rats") && log.start("far") && log.start("shrooms"));
+        assert!(!log.start("rats"));
+
+        assert!(log.on_event(&all, Event::Killed("goblin")).is_empty());
+        assert!(log.on_event(&all, Event::Killed("rat")).is_empty());
+        assert_eq!(log.on_event(&all, Event::Killed("rat")), vec!["rats"]);
+        assert_eq!(log.stage("rats"), Stage::Done);
+        // Finished quests don't count any more
+        assert!(log.on_event(&all, Event::Killed("rat")).is_empty());
+
+        assert!(log.on_event(&all, Event::Entered(3, 1)).is_empty());
+        assert_eq!(log.on_event(&all, Event::Entered(3, -1)), vec!["far"]);
+
+        // Fetching follows what's carried, so dropping things counts too
+        log.on_event(&all, Event::Carrying("strange_mushroom", 1));
+        log.on_event(&all, Event::Carrying("strange_mushroom", 0));
+        assert_eq!(log.active[0].count, 0);
+        assert_eq!(log.on_event(&all, Event::Carrying("strange_mushroom", 2)), vec!["shrooms"]);
+    }
+}
@@ -0,0 +1,71 @@
+// shop.rs
+//
+// Merchant stock and prices. Merchants roll their stock from a loot table
+// and roll it again once enough time has passed.
+use crate::item::Item;
+
+/// Turns between restocks.
+pub const RESTOCK_TURNS: u64 = 500;
+
+#[derive(Clone, Debug)]
+pub struct Shop {
+    /// Loot table the stock is rolled from.
+    pub table: String,
+    pub stock: Vec<Item>,
+    /// Turn the stock was last rolled.
+    pub restocked_at: u64,
+}
+
+impl Shop {
+    pub fn new(table: &str, stock: Vec<Item>, turn: u64) -> Self {
+        Self {
+            table: table.to_string(),
+            stock,
+            restocked_at: turn,
+        }
+    }
+
+    pub fn needs_restock(&self, turn: u64) -> bool {
+        turn >= self.restocked_at + RESTOCK_TURNS
+    }
+
+    /// Replaces the stock, dropping anything the player sold here.
+    pub fn restock(&mut self, stock: Vec<Item>, turn: u64) {
+        self.stock = stock;
+        self.restocked_at = turn;
+    }
+}
+
+/// What a merchant charges for an item.
+pub fn buy_price(item: &Item) -> i32 {
+    item.value.max(1)
+}
+
+/// What a merchant pays for an item: half its value.
+pub fn sell_price(item: &Item) -> i32 {
+    item.value / 2
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::item;
+
+    #[test]
+    fn test_prices() {
+        let sword = item::make("sword").unwrap();
+        assert_eq!((buy_price(&sword), sell_price(&sword)), (40, 20));
+        let mushroom = item::make("strange_mushroom").unwrap();
+        assert_eq!((buy_price(&mushroom), sell_price(&mushroom)), (4, 2));
+    }
+
+    #[test]
+    fn test_restock_timer() {
+        let mut shop = Shop::new("stock", Vec::new(), 100);
+        assert!(!shop.needs_restock(100 + RESTOCK_TURNS - 1));
+        assert!(shop.needs_restock(100 + RESTOCK_TURNS));
+        shop.restock(vec![item::make("dagger").unwrap()], 700);
+        assert!(!shop.needs_restock(700));
+        assert_eq!(shop.stock.len(), 1);
+    }
+}
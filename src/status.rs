@@ -0,0 +1,267 @@
+// status.rs
+//
+// Timed effects on actors. Durations count the bearer's own turns, so a
+// hasted monster burns through its haste faster in world time.
+use macroquad::rand;
+use crate::combat::Fighter;
+use crate::geometry::Direction;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum StatusKind {
+    /// Loses `potency` HP at the start of each turn.
+    Poisoned,
+    /// Gains energy twice as fast.
+    Hasted,
+    /// Gains energy half as fast.
+    Slowed,
+    /// Recovers `potency` HP at the start of each turn.
+    Regenerating,
+    /// Can't see past adjacent tiles.
+    Blind,
+    /// Stumbles in a random direction half the time.
+    Confused,
+}
+
+/// How a new effect combines with one of the same kind already active.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Stacking {
+    /// Potency adds up; duration becomes the longer of the two.
+    Intensify,
+    /// Potency and duration both become the larger of the two.
+    Refresh,
+    /// Durations add up.
+    Extend,
+}
+
+impl StatusKind {
+    pub const ALL: [StatusKind; 6] = [
+        StatusKind::Poisoned,
+        StatusKind::Hasted,
+        StatusKind::Slowed,
+        StatusKind::Regenerating,
+        StatusKind::Blind,
+        StatusKind::Confused,
+    ];
+
+    pub fn from_name(name: &str) -> Option<StatusKind> {
+        Self::ALL.into_iter().find(|k| k.name() == name)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            StatusKind::Poisoned => "poisoned",
+            StatusKind::Hasted => "hasted",
+            StatusKind::Slowed => "slowed",
+            StatusKind::Regenerating => "regenerating",
+            StatusKind::Blind => "blind",
+            StatusKind::Confused => "confused",
+        }
+    }
+
+    fn stacking(self) -> Stacking {
+        match self {
+            StatusKind::Poisoned => Stacking::Intensify,
+            StatusKind::Regenerating => Stacking::Refresh,
+            StatusKind::Hasted | StatusKind::Slowed => Stacking::Refresh,
+            StatusKind::Blind | StatusKind::Confused => Stacking::Extend,
+        }
+    }
+
+    /// The effect this one cancels out when applied, if any.
+    fn opposite(self) -> Option<StatusKind> {
+        match self {
+            StatusKind::Hasted => Some(StatusKind::Slowed),
+            StatusKind::Slowed => Some(StatusKind::Hasted),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StatusEffect {
+    pub kind: StatusKind,
+    /// Turns remaining.
+    pub turns: i32,
+    /// Strength, for effects that have one (damage or healing per turn).
+    pub potency: i32,
+    /// Whether the player put it there, so its kills are theirs.
+    pub from_player: bool,
+}
+
+impl StatusEffect {
+    pub const fn new(kind: StatusKind, turns: i32, potency: i32) -> Self {
+        Self { kind, turns, potency, from_player: false }
+    }
+
+    pub fn by_player(mut self) -> Self {
+        self.from_player = true;
+        self
+    }
+}
+
+/// Everything currently affecting one actor.
+#[derive(Clone, Debug, Default)]
+pub struct StatusEffects {
+    effects: Vec<StatusEffect>,
+}
+
+impl StatusEffects {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &StatusEffect> {
+        self.effects.iter()
+    }
+
+    pub fn has(&self, kind: StatusKind) -> bool {
+        self.effects.iter().any(|e| e.kind == kind)
+    }
+
+    /// Applies an effect, following that kind's stacking rule. Opposing
+    /// effects (haste and slow) cancel each other instead of stacking.
+    pub fn add(&mut self, effect: StatusEffect) {
+        if let Some(opposite) = effect.kind.opposite()
+            && self.has(opposite)
+        {
+            self.effects.retain(|e| e.kind != opposite);
+            return;
+        }
+
+        let Some(existing) = self.effects.iter_mut().find(|e| e.kind == effect.kind) else {
+            self.effects.push(effect);
+            return;
+        };
+        existing.from_player |= effect.from_player;
+        match effect.kind.stacking() {
+            Stacking::Intensify => {
+                existing.potency += effect.potency;
+                existing.turns = existing.turns.max(effect.turns);
+            }
+            Stacking::Refresh => {
+                existing.potency = existing.potency.max(effect.potency);
+                existing.turns = existing.turns.max(effect.turns);
+            }
+            Stacking::Extend => existing.turns += effect.turns,
+        }
+    }
+
+    /// Start-of-turn hook: poison and regeneration. Returns the net HP change.
+    pub fn on_turn_start(&self, fighter: &mut Fighter) -> i32 {
+        let before = fighter.hp;
+        for effect in &self.effects {
+            match effect.kind {
+                StatusKind::Poisoned => fighter.hp -= effect.potency,
+                StatusKind::Regenerating => fighter.hp = (fighter.hp + effect.potency).min(fighter.max_hp),
+                _ => {}
+            }
+        }
+        fighter.hp - before
+    }
+
+    /// Whether the player had a hand in the damage `on_turn_start` deals.
+    pub fn harmed_by_player(&self) -> bool {
+        self.effects.iter().any(|e| e.kind == StatusKind::Poisoned && e.from_player)
+    }
+
+    /// End-of-turn hook: counts every effect down and drops the expired ones,
+    /// which are returned so the caller can report them.
+    pub fn on_turn_end(&mut self) -> Vec<StatusKind> {
+        for effect in &mut self.effects {
+            effect.turns -= 1;
+        }
+        let expired = self.effects.iter().filter(|e| e.turns <= 0).map(|e| e.kind).collect();
+        self.effects.retain(|e| e.turns > 0);
+        expired
+    }
+
+    /// Movement hook: a confused actor goes the wrong way half the time.
+    pub fn on_move(&self, intended: Direction) -> Direction {
+        if self.has(StatusKind::Confused) && rand::gen_range(0, 2) == 0 {
+            Direction::CARDINALS[rand::gen_range(0, 4)]
+        } else {
+            intended
+        }
+    }
+
+    /// Energy gained per tick after haste and slow.
+    pub fn adjust_speed(&self, speed: i32) -> i32 {
+        if self.has(StatusKind::Hasted) {
+            speed * 2
+        } else if self.has(StatusKind::Slowed) {
+            (speed / 2).max(1)
+        } else {
+            speed
+        }
+    }
+
+    /// Sight radius after blindness.
+    pub fn adjust_sight(&self, radius: i32) -> i32 {
+        if self.has(StatusKind::Blind) { radius.min(1) } else { radius }
+    }
+
+    /// Short HUD summary like "poisoned(3) hasted(12)".
+    pub fn describe(&self) -> String {
+        self.effects
+            .iter()
+            .map(|e| format!("{}({})", e.kind.name(), e.turns))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stacking_rules() {
+        let mut effects = StatusEffects::new();
+        effects.add(StatusEffect::new(StatusKind::Poisoned, 5, 1));
+        effects.add(StatusEffect::new(StatusKind::Poisoned, 3, 2));
+        let poison = effects.iter().find(|e| e.kind == StatusKind::Poisoned).unwrap();
+        assert_eq!((poison.turns, poison.potency), (5, 3));
+
+        effects.add(StatusEffect::new(StatusKind::Confused, 4, 0));
+        effects.add(StatusEffect::new(StatusKind::Confused, 4, 0));
+        let confused = effects.iter().find(|e| e.kind == StatusKind::Confused).unwrap();
+        assert_eq!(confused.turns, 8);
+    }
+
+    #[test]
+    fn test_player_poison_stays_theirs() {
+        let mut effects = StatusEffects::new();
+        effects.add(StatusEffect::new(StatusKind::Confused, 4, 0).by_player());
+        effects.add(StatusEffect::new(StatusKind::Poisoned, 5, 1));
+        assert!(!effects.harmed_by_player());
+        effects.add(StatusEffect::new(StatusKind::Poisoned, 2, 1).by_player());
+        effects.add(StatusEffect::new(StatusKind::Poisoned, 2, 1));
+        assert!(effects.harmed_by_player());
+    }
+
+    #[test]
+    fn test_haste_cancels_slow() {
+        let mut effects = StatusEffects::new();
+        effects.add(StatusEffect::new(StatusKind::Slowed, 10, 0));
+        assert_eq!(effects.adjust_speed(10), 5);
+
+        effects.add(StatusEffect::new(StatusKind::Hasted, 10, 0));
+        assert!(!effects.has(StatusKind::Slowed));
+        assert!(!effects.has(StatusKind::Hasted));
+        assert_eq!(effects.adjust_speed(10), 10);
+    }
+
+    #[test]
+    fn test_turn_hooks() {
+        let mut effects = StatusEffects::new();
+        let mut fighter = Fighter::new(10, 1, 0, 50);
+        effects.add(StatusEffect::new(StatusKind::Poisoned, 2, 3));
+
+        assert_eq!(effects.on_turn_start(&mut fighter), -3);
+        assert!(effects.on_turn_end().is_empty());
+        assert_eq!(effects.on_turn_start(&mut fighter), -3);
+        assert_eq!(effects.on_turn_end(), vec![StatusKind::Poisoned]);
+        assert_eq!(fighter.hp, 4);
+        assert_eq!(effects.iter().count(), 0);
+    }
+}
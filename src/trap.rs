@@ -0,0 +1,139 @@
+// trap.rs
+//
+// Traps and secret doors: features generators hide in a chunk that look like
+// plain floor or wall until the player finds them, either by searching or by
+// noticing them in passing.
+use macroquad::prelude::*;
+
+/// How far from the player a search reaches, in tiles (Chebyshev).
+pub const SEARCH_RADIUS: i32 = 2;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Trap {
+    /// Shoots a dart at whoever steps on it.
+    Dart,
+    /// Drops the player into deeper, more dangerous ground.
+    Pit,
+    /// Rings out loud enough to wake everything nearby.
+    Alarm,
+    /// Sends the player somewhere else nearby.
+    Teleport,
+}
+
+impl Trap {
+    pub fn name(self) -> &'static str {
+        match self {
+            Trap::Dart => "dart trap",
+            Trap::Pit => "pit",
+            Trap::Alarm => "alarm trap",
+            Trap::Teleport => "teleport trap",
+        }
+    }
+
+    /// Colour of the `^` drawn once it's been found.
+    pub fn color(self) -> Color {
+        match self {
+            Trap::Dart => LIGHTGRAY,
+            Trap::Pit => BROWN,
+            Trap::Alarm => YELLOW,
+            Trap::Teleport => VIOLET,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Feature {
+    /// Lies under a floor tile.
+    Trap(Trap),
+    /// Looks like wall; becomes a door once found.
+    SecretDoor,
+}
+
+impl Feature {
+    /// Name used in saved chunks.
+    pub fn id(self) -> &'static str {
+        match self {
+            Feature::Trap(Trap::Dart) => "dart",
+            Feature::Trap(Trap::Pit) => "pit",
+            Feature::Trap(Trap::Alarm) => "alarm",
+            Feature::Trap(Trap::Teleport) => "teleport",
+            Feature::SecretDoor => "secret_door",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Self> {
+        Some(match id {
+            "dart" => Feature::Trap(Trap::Dart),
+            "pit" => Feature::Trap(Trap::Pit),
+            "alarm" => Feature::Trap(Trap::Alarm),
+            "teleport" => Feature::Trap(Trap::Teleport),
+            "secret_door" => Feature::SecretDoor,
+            _ => return None,
+        })
+    }
+}
+
+/// A feature hidden in one tile of a chunk.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Hidden {
+    pub feature: Feature,
+    /// Whether the player has found it.
+    pub found: bool,
+}
+
+impl Hidden {
+    pub fn new(feature: Feature) -> Self {
+        Self { feature, found: false }
+    }
+
+    /// The trap here, if there is one and the player knows about it.
+    pub fn known_trap(&self) -> Option<Trap> {
+        match self.feature {
+            Feature::Trap(trap) if self.found => Some(trap),
+            _ => None,
+        }
+    }
+}
+
+/// Percent chance a search turns up each hidden feature in range.
+pub fn search_chance(search: i32) -> i32 {
+    (40 + 10 * search).clamp(10, 95)
+}
+
+/// Percent chance each turn of noticing a hidden feature in range without
+/// searching for it.
+pub fn notice_chance(search: i32) -> i32 {
+    (2 * search).clamp(0, 40)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_feature_ids_round_trip() {
+        let all = [
+            Feature::Trap(Trap::Dart),
+            Feature::Trap(Trap::Pit),
+            Feature::Trap(Trap::Alarm),
+            Feature::Trap(Trap::Teleport),
+            Feature::SecretDoor,
+        ];
+        for feature in all {
+            assert_eq!(Feature::from_id(feature.id()), Some(feature));
+        }
+        assert_eq!(Feature::from_id("bear_trap"), None);
+    }
+
+    #[test]
+    fn test_searching_beats_noticing() {
+        for search in -5..20 {
+            assert!(search_chance(search) > notice_chance(search));
+        }
+        let mut hidden = Hidden::new(Feature::Trap(Trap::Pit));
+        assert_eq!(hidden.known_trap(), None);
+        hidden.found = true;
+        assert_eq!(hidden.known_trap(), Some(Trap::Pit));
+        assert_eq!(Hidden { found: true, ..Hidden::new(Feature::SecretDoor) }.known_trap(), None);
+    }
+}
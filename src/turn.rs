@@ -0,0 +1,107 @@
+// turn.rs
+//
+// Energy-based scheduling. Every tick each actor gains energy equal to its
+// speed; once it has `ACTION_THRESHOLD` it may act, and acting spends the
+// action's cost. Fast actors therefore act more often and cheap actions come
+// round sooner.
+
+/// Energy an actor needs before it can take an action.
+pub const ACTION_THRESHOLD: i32 = 100;
+/// Speed of an ordinary actor, the player included.
+pub const NORMAL_SPEED: i32 = 10;
+/// Ticks in one game turn, i.e. the time a normal-speed actor takes to act.
+pub const TICKS_PER_TURN: u64 = (ACTION_THRESHOLD / NORMAL_SPEED) as u64;
+
+/// Anything that takes time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ActionKind {
+    Move,
+    Wait,
+    Attack,
+    PickUp,
+    Drop,
+    UseItem,
+    Equip,
+    /// Firing or throwing a projectile.
+    Shoot,
+    /// Using an ability.
+    Cast,
+    /// Looking around for hidden things.
+    Search,
+}
+
+impl ActionKind {
+    /// Energy spent performing this action.
+    pub fn cost(self) -> i32 {
+        match self {
+            ActionKind::Move => 100,
+            ActionKind::Wait => 100,
+            ActionKind::Attack => 100,
+            ActionKind::PickUp => 50,
+            ActionKind::Drop => 50,
+            ActionKind::UseItem => 100,
+            ActionKind::Equip => 100,
+            ActionKind::Shoot => 100,
+            ActionKind::Cast => 100,
+            ActionKind::Search => 100,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Energy {
+    pub speed: i32,
+    pub energy: i32,
+}
+
+impl Energy {
+    /// Starts ready to act, so nothing gets a free first move.
+    pub fn new(speed: i32) -> Self {
+        Self {
+            speed,
+            energy: ACTION_THRESHOLD,
+        }
+    }
+
+    /// Adds one tick's energy. `speed` is the actor's speed after any haste
+    /// or slow, which is why it isn't simply `self.speed`.
+    pub fn gain(&mut self, speed: i32) {
+        self.energy += speed;
+    }
+
+    pub fn can_act(&self) -> bool {
+        self.energy >= ACTION_THRESHOLD
+    }
+
+    pub fn spend(&mut self, action: ActionKind) {
+        self.energy -= action.cost();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Counts how many actions an actor gets over `ticks` ticks.
+    fn actions_in(speed: i32, ticks: u32) -> u32 {
+        let mut energy = Energy::new(speed);
+        energy.spend(ActionKind::Move);
+        let mut actions = 0;
+        for _ in 0..ticks {
+            energy.gain(speed);
+            while energy.can_act() {
+                energy.spend(ActionKind::Move);
+                actions += 1;
+            }
+        }
+        actions
+    }
+
+    #[test]
+    fn test_speed_scales_actions() {
+        let ticks = TICKS_PER_TURN as u32 * 10;
+        assert_eq!(actions_in(NORMAL_SPEED, ticks), 10);
+        assert_eq!(actions_in(NORMAL_SPEED * 2, ticks), 20);
+        assert_eq!(actions_in(NORMAL_SPEED / 2, ticks), 5);
+    }
+}
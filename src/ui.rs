@@ -0,0 +1,111 @@
+// ui.rs
+//
+// Overlay widgets drawn on top of the map.
+use macroquad::prelude::*;
+use crate::game_state::{CHAR_HEIGHT, CHAR_WIDTH, VIEWPORT_HEIGHT, VIEWPORT_WIDTH};
+
+const LINE_HEIGHT: f32 = 20.0;
+const FONT_SIZE: f32 = 18.0;
+
+/// A menu shows one entry per letter; anything past 'z' waits until earlier
+/// entries are used up.
+pub const MAX_MENU_ENTRIES: usize = 26;
+
+/// Label for the `index`th menu entry: 'a', 'b', ...
+pub fn letter(index: usize) -> char {
+    debug_assert!(index < MAX_MENU_ENTRIES, "menu entry {} has no letter", index);
+    (b'a' + index as u8) as char
+}
+
+/// The menu entry a typed letter selects, if it's in range and shown.
+pub fn letter_index(c: char, len: usize) -> Option<usize> {
+    let index = (c as usize).checked_sub('a' as usize)?;
+    (index < len.min(MAX_MENU_ENTRIES)).then_some(index)
+}
+
+/// Splits `text` into lines of at most `width` characters, breaking between
+/// words.
+pub fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.len() + 1 + word.len() > width {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// Draws a boxed, lettered list of options centred over the map.
+pub fn draw_menu(title: &str, options: &[String], footer: &str) {
+    let mut lines: Vec<String> = options
+        .iter()
+        .take(MAX_MENU_ENTRIES)
+        .enumerate()
+        .map(|(i, o)| format!("({}) {}", letter(i), o))
+        .collect();
+    if options.len() > MAX_MENU_ENTRIES {
+        lines.push(format!("...and {} more", options.len() - MAX_MENU_ENTRIES));
+    }
+    draw_panel(title, &lines, footer);
+}
+
+/// Draws a boxed panel of plain text lines centred over the map.
+pub fn draw_panel(title: &str, lines: &[String], footer: &str) {
+    let map_w = VIEWPORT_WIDTH as f32 * CHAR_WIDTH;
+    let map_h = VIEWPORT_HEIGHT as f32 * CHAR_HEIGHT;
+
+    let widest = lines
+        .iter()
+        .map(|l| l.as_str())
+        .chain([title, footer])
+        .map(|l| measure_text(l, None, FONT_SIZE as u16, 1.0).width)
+        .fold(0.0, f32::max);
+    let w = (widest + 40.0).min(map_w - 20.0);
+    let h = (lines.len() as f32 + 3.0) * LINE_HEIGHT + 10.0;
+    let x = (map_w - w) / 2.0;
+    let y = ((map_h - h) / 2.0).max(10.0);
+
+    draw_rectangle(x, y, w, h, Color::new(0.05, 0.05, 0.1, 0.95));
+    draw_rectangle_lines(x, y, w, h, 2.0, GRAY);
+
+    draw_text(title, x + 20.0, y + LINE_HEIGHT + 4.0, FONT_SIZE, YELLOW);
+    for (i, line) in lines.iter().enumerate() {
+        let ly = y + (i as f32 + 2.0) * LINE_HEIGHT + 4.0;
+        draw_text(line, x + 20.0, ly, FONT_SIZE, WHITE);
+    }
+    draw_text(footer, x + 20.0, y + h - 10.0, FONT_SIZE - 2.0, GRAY);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_letters_round_trip() {
+        assert_eq!(letter(0), 'a');
+        assert_eq!(letter(3), 'd');
+        assert_eq!(letter_index('d', 4), Some(3));
+        assert_eq!(letter_index('e', 4), None);
+        assert_eq!(letter_index('A', 4), None);
+        // Entries past 'z' can't be picked
+        assert_eq!(letter(MAX_MENU_ENTRIES - 1), 'z');
+        assert_eq!(letter_index('z', 40), Some(25));
+        assert_eq!(letter_index('{', 40), None);
+    }
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(wrap("the quick brown fox", 9), vec!["the quick", "brown fox"]);
+        assert_eq!(wrap("  spaced   out ", 20), vec!["spaced out"]);
+        assert_eq!(wrap("unbreakableword", 4), vec!["unbreakableword"]);
+        assert!(wrap("", 10).is_empty());
+    }
+}